@@ -0,0 +1,99 @@
+#![no_main]
+//
+// Differential fuzzing of the counts of wc against a simple reference implementation.
+// Counting is part of the wc binary, so every input is sent as raw data to one
+// `wc --daemon`, which counts it exactly like a file. Build wc first:
+//
+//     cargo build && cargo fuzz run count
+//
+// WC_BIN overrides the path of the binary, target/debug/wc. With WC_FUZZ_SYSTEM set,
+// lines and bytes are also compared with those of the system wc.
+//
+use libfuzzer_sys::fuzz_target;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::os::unix::net::UnixStream;
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+struct Daemon {
+    _process: Child,
+    connection: BufReader<UnixStream>,
+}
+
+static DAEMON: OnceLock<Mutex<Daemon>> = OnceLock::new();
+
+fn daemon() -> &'static Mutex<Daemon> {
+    DAEMON.get_or_init(|| {
+        let wc = std::env::var("WC_BIN")
+            .unwrap_or_else(|_| concat!(env!("CARGO_MANIFEST_DIR"), "/../target/debug/wc").into());
+        let socket = std::env::temp_dir().join(format!("wc-fuzz-{}.sock", std::process::id()));
+        let mut command = Command::new(wc);
+        command.arg("--daemon").arg(&socket);
+        // SAFETY: prctl only changes a flag of the child, which is stopped with the fuzzer
+        #[cfg(target_os = "linux")]
+        unsafe {
+            command.pre_exec(|| {
+                libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM);
+                Ok(())
+            });
+        }
+        let process = command.spawn().expect("cannot start wc, build it first");
+        let stream = loop {
+            match UnixStream::connect(&socket) {
+                Ok(stream) => break stream,
+                Err(_) => std::thread::sleep(Duration::from_millis(10)),
+            }
+        };
+        Mutex::new(Daemon {
+            _process: process,
+            connection: BufReader::new(stream),
+        })
+    })
+}
+
+// The counts of wc for the data, as JSON
+fn count(data: &[u8]) -> serde_json::Value {
+    let mut daemon = daemon().lock().unwrap();
+    let stream = daemon.connection.get_mut();
+    writeln!(stream, ":{}", data.len()).unwrap();
+    stream.write_all(data).unwrap();
+    let mut reply = String::new();
+    daemon.connection.read_line(&mut reply).unwrap();
+    serde_json::from_str(&reply).unwrap()
+}
+
+// Lines and bytes as counted by the system wc
+fn count_with_system_wc(data: &[u8]) -> (usize, usize) {
+    let file = std::env::temp_dir().join(format!("wc-fuzz-{}.input", std::process::id()));
+    std::fs::write(&file, data).unwrap();
+    let output = Command::new("wc").arg("-lc").arg(&file).output().unwrap();
+    let output = String::from_utf8(output.stdout).unwrap();
+    let mut counts = output.split_whitespace().map(|n| n.parse().unwrap());
+    (counts.next().unwrap(), counts.next().unwrap())
+}
+
+fuzz_target!(|data: &[u8]| {
+    let m = count(data);
+    let get = |name: &str| match m[name].as_u64() {
+        Some(n) => n as usize,
+        None => panic!("no {} in {}", name, m),
+    };
+    assert_eq!(get("bytes"), data.len());
+    // UTF-16 is decoded before lines are counted
+    let utf16 = data.starts_with(&[0xff, 0xfe]) || data.starts_with(&[0xfe, 0xff]);
+    if !utf16 {
+        assert_eq!(get("lines"), data.iter().filter(|&&b| b == b'\n').count());
+    }
+    if let Ok(text) = std::str::from_utf8(data) {
+        if !text.starts_with('\u{feff}') {
+            assert_eq!(get("chars"), text.chars().count());
+            assert_eq!(get("words"), text.split_whitespace().count());
+        }
+    }
+    if std::env::var_os("WC_FUZZ_SYSTEM").is_some() && !utf16 {
+        assert_eq!(count_with_system_wc(data), (get("lines"), get("bytes")));
+    }
+});
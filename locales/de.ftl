@@ -0,0 +1,38 @@
+# Beschriftungen in der Spalte der Dateinamen
+total = insgesamt
+subtotal = Zwischensumme
+difference = Differenz
+partial = { $label } (unvollständig)
+no-text = { $file } (kein Text)
+unique-margin = { $label } (verschiedene Wörter ±{ $margin })
+estimated = { $label } (geschätzt, ±{ $lines } Zeilen, ±{ $words } Wörter, ±{ $chars } Zeichen)
+rate = { $label } ({ $lines } Zeilen/s, { $bytes } Bytes/s)
+new-file = { $file } (neu)
+removed-file = { $file } (entfernt)
+changed-file = { $file } (geändert)
+
+# Ob eine erratene Kodierung sicher ist
+encoding-certain = sicher
+encoding-uncertain = unsicher
+
+# Meldungen auf stderr, meist nach "wc: <Datei>: "
+skipped-special = übersprungen, keine reguläre Datei
+skipped-larger = übersprungen, größer als { $max } Bytes
+empty = leer
+no-lines = keine Zeilen
+inconsistent-fields = uneinheitliche Anzahl Felder: { $min } bis { $max } pro Datensatz, meist { $modal }
+vocabulary-pruned = WARNUNG: Wortschatz größer als --max-memory, seltene Wörter ausgelassen
+dupes-approximate = WARNUNG: gemeinsame Zeilen brauchen mehr als --max-memory, Zählungen sind ungefähr
+interrupted = unterbrochen, die Zählungen sind unvollständig
+progress = { $files } Dateien fertig, { $bytes } Bytes gelesen, zähle { $file }
+improperly-formatted = WARNUNG: { $count ->
+    [one] { $count } Zeile ist
+   *[other] { $count } Zeilen sind
+} falsch formatiert
+not-matched = WARNUNG: { $count ->
+    [one] { $count } Zählung stimmt
+   *[other] { $count } Zählungen stimmen
+} NICHT überein
+timed-out = nach { $seconds } s abgebrochen
+stats = { $files } Dateien gezählt, { $skipped } übersprungen, { $errors } nicht lesbar, { $bytes } Bytes in { $seconds } s ({ $throughput } MB/s)
+interactive-prompt = Text eingeben, mit einer Zeile nur aus '.' oder Strg-D abschließen:
@@ -0,0 +1,32 @@
+# Labels in the file name column
+total = total
+subtotal = subtotal
+difference = difference
+partial = { $label } (partial)
+no-text = { $file } (no text)
+unique-margin = { $label } (unique words ±{ $margin })
+estimated = { $label } (estimated, ±{ $lines } lines, ±{ $words } words, ±{ $chars } characters)
+rate = { $label } ({ $lines } lines/s, { $bytes } bytes/s)
+new-file = { $file } (new)
+removed-file = { $file } (removed)
+changed-file = { $file } (changed)
+
+# Whether a guessed encoding is certain
+encoding-certain = certain
+encoding-uncertain = uncertain
+
+# Messages on stderr, most of them following "wc: <file>: "
+skipped-special = skipped, not a regular file
+skipped-larger = skipped, larger than { $max } bytes
+empty = empty
+no-lines = no lines
+inconsistent-fields = inconsistent number of fields: { $min } to { $max } per record, mostly { $modal }
+vocabulary-pruned = WARNING: vocabulary larger than --max-memory, rare words left out
+dupes-approximate = WARNING: shared lines take more than --max-memory, counts are approximate
+interrupted = interrupted, the counts are partial
+progress = { $files } files done, { $bytes } bytes read, counting { $file }
+improperly-formatted = WARNING: { $count } lines are improperly formatted
+not-matched = WARNING: { $count } counts did NOT match
+timed-out = timed out after { $seconds } s
+stats = { $files } files counted, { $skipped } skipped, { $errors } unreadable, { $bytes } bytes in { $seconds } s ({ $throughput } MB/s)
+interactive-prompt = Enter text, finish with a line containing only '.' or Ctrl-D:
@@ -0,0 +1,207 @@
+use crate::{Metrics, ShowOptions};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Error};
+
+//
+// Write the counts of all files as a JSON array.
+//
+pub fn save(baseline: &str, all_metrics: &[Metrics]) -> Result<(), Error> {
+    let writer = BufWriter::new(File::create(baseline)?);
+    serde_json::to_writer_pretty(writer, all_metrics)?;
+    Ok(())
+}
+
+fn load(baseline: &str) -> Result<Vec<Metrics>, Error> {
+    let reader = BufReader::new(File::open(baseline)?);
+    Ok(serde_json::from_reader(reader)?)
+}
+
+// One row of the comparison: a signed difference per column and the file name
+fn deltas(now: &Metrics, then: &Metrics, opts: &ShowOptions) -> Vec<String> {
+    opts.columns()
+        .iter()
+        .map(|column| {
+            let delta = column.value(now) as i64 - column.value(then) as i64;
+            format!("{:+} {}", delta, column.name())
+        })
+        .collect()
+}
+
+// Print the rows in columns, each row ending with its file name
+fn print_rows(
+    out: &mut dyn io::Write,
+    rows: Vec<(Vec<String>, String)>,
+    opts: &ShowOptions,
+) -> Result<(), Error> {
+    let mut widths = vec![0; opts.columns().len()];
+    for (cells, _) in &rows {
+        for (width, cell) in widths.iter_mut().zip(cells) {
+            *width = std::cmp::max(*width, cell.len());
+        }
+    }
+    for (cells, filename) in rows {
+        for (width, cell) in widths.iter().zip(cells) {
+            write!(out, "{:>width$}  ", cell, width = width)?;
+        }
+        writeln!(out, "{}", filename)?;
+    }
+    Ok(())
+}
+
+// Which of the files that are both in the baseline and counted now are listed
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Listing {
+    All,
+    // Those whose counts changed
+    ChangedCounts,
+    // Those whose content changed, by its hash if there is one, marked as changed
+    ChangedContent,
+}
+
+// Whether the content of a file changed, by its hash if both have one, else by its counts
+fn changed(now: &Metrics, then: &Metrics, opts: &ShowOptions) -> bool {
+    match (&now.hash, &then.hash) {
+        (Some(now), Some(then)) => now != then,
+        _ => opts.selected(now) != opts.selected(then),
+    }
+}
+
+// The rows of the files that are listed, those that were added or removed, and of the total
+fn changes(
+    previous: &[Metrics],
+    all_metrics: &[Metrics],
+    opts: &ShowOptions,
+    listing: Listing,
+) -> Vec<(Vec<String>, String)> {
+    let label = |id: &str, file: &str| crate::i18n::message(id, &[("file", file.into())]);
+    let none = Metrics::default();
+    let mut rows = vec![];
+    for m in all_metrics {
+        match previous.iter().find(|p| p.filename == m.filename) {
+            Some(p) => match listing {
+                Listing::ChangedCounts if opts.selected(m) == opts.selected(p) => (),
+                Listing::ChangedContent if !changed(m, p, opts) => (),
+                Listing::ChangedContent => {
+                    rows.push((deltas(m, p, opts), label("changed-file", &m.filename)))
+                }
+                _ => rows.push((deltas(m, p, opts), m.filename.clone())),
+            },
+            None => rows.push((deltas(m, &none, opts), label("new-file", &m.filename))),
+        }
+    }
+    for p in previous {
+        if !all_metrics.iter().any(|m| m.filename == p.filename) {
+            rows.push((deltas(&none, p, opts), label("removed-file", &p.filename)));
+        }
+    }
+    if rows.len() > 1 {
+        let (now, _) = crate::calculate_total_and_max_width_per_column(all_metrics);
+        let (then, _) = crate::calculate_total_and_max_width_per_column(previous);
+        rows.push((deltas(&now, &then, opts), opts.total_label()));
+    }
+    rows
+}
+
+//
+// Print how the counts changed compared to a baseline saved earlier.
+// Files that were added or removed since are marked as such.
+// With `changed_only`, files whose counts did not change are left out.
+//
+pub fn diff(
+    out: &mut dyn io::Write,
+    baseline: &str,
+    all_metrics: &[Metrics],
+    opts: &ShowOptions,
+    changed_only: bool,
+) -> Result<(), Error> {
+    let previous = load(baseline)?;
+    let listing = if changed_only {
+        Listing::ChangedCounts
+    } else {
+        Listing::All
+    };
+    print_rows(out, changes(&previous, all_metrics, opts, listing), opts)
+}
+
+//
+// Print the files that were added, removed or changed since a snapshot was saved,
+// with how their counts changed. A file may have changed although its counts did not.
+//
+pub fn since(
+    out: &mut dyn io::Write,
+    snapshot: &str,
+    all_metrics: &[Metrics],
+    opts: &ShowOptions,
+) -> Result<(), Error> {
+    let previous = load(snapshot)?;
+    let rows = changes(&previous, all_metrics, opts, Listing::ChangedContent);
+    print_rows(out, rows, opts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_deltas() {
+        let then = Metrics {
+            lines: 100,
+            words: 50,
+            bytes: 700,
+            ..Default::default()
+        };
+        let now = Metrics {
+            lines: 220,
+            words: 47,
+            bytes: 700,
+            ..Default::default()
+        };
+        let opts = ShowOptions::default();
+        assert_eq!(
+            deltas(&now, &then, &opts),
+            ["+120 lines", "-3 words", "+0 bytes"]
+        );
+    }
+
+    #[test]
+    fn older_baseline() {
+        // Saved before records, documents and the other counts were added
+        let json = r#"[{"bytes": 43, "chars": 43, "lines": 9, "words": 7,
+                        "max_line_length": 10, "graphemes": 43, "filename": "tests/abc.txt"}]"#;
+        let previous: Vec<Metrics> = serde_json::from_str(json).unwrap();
+        assert_eq!(previous[0].lines, 9);
+        assert_eq!(previous[0].records, 0);
+        assert_eq!(previous[0].unique_words, 0);
+        assert_eq!(previous[0].hash, None);
+    }
+
+    #[test]
+    fn changes_since() {
+        let file = |filename: &str, lines: usize, hash: &str| Metrics {
+            lines,
+            bytes: lines * 10,
+            filename: filename.to_owned(),
+            hash: Some(hash.to_owned()),
+            ..Default::default()
+        };
+        let then = [file("a", 1, "01"), file("b", 2, "02"), file("c", 3, "03")];
+        let now = [file("a", 1, "01"), file("b", 2, "12"), file("d", 4, "04")];
+        let mut out = vec![];
+        let opts = ShowOptions {
+            total_label: Some("corpus".to_owned()),
+            ..Default::default()
+        };
+        let rows = changes(&then, &now, &opts, Listing::ChangedContent);
+        print_rows(&mut out, rows, &opts).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "+0 lines  +0 words   +0 bytes  b (changed)\n\
+             +4 lines  +0 words  +40 bytes  d (new)\n\
+             -3 lines  +0 words  -30 bytes  c (removed)\n\
+             +1 lines  +0 words  +10 bytes  corpus\n"
+        );
+        let rows = changes(&then, &now, &opts, Listing::ChangedCounts);
+        let files: Vec<&str> = rows.iter().map(|(_, file)| file.as_str()).collect();
+        assert_eq!(files, ["d (new)", "c (removed)", "corpus"]);
+    }
+}
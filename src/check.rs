@@ -0,0 +1,140 @@
+use crate::{Column, CountOptions, ShowOptions};
+use std::fs;
+use std::io::{Error, ErrorKind};
+
+//
+// Split a line of wc output into its counts and the file name.
+// The file name is everything after the expected number of counts,
+// so names containing spaces survive.
+//
+fn parse_line(line: &str, columns: usize) -> Option<(Vec<usize>, &str)> {
+    let mut rest = line.trim_start();
+    let mut counts = Vec::with_capacity(columns);
+    for _ in 0..columns {
+        let end = rest.find(char::is_whitespace)?;
+        counts.push(rest[..end].parse().ok()?);
+        rest = rest[end..].trim_start();
+    }
+    if rest.is_empty() {
+        None
+    } else {
+        Some((counts, rest))
+    }
+}
+
+// Describe every column in which the counts differ
+fn mismatches(columns: &[Column], expected: &[usize], counted: &[usize]) -> Vec<String> {
+    columns
+        .iter()
+        .zip(expected.iter().zip(counted))
+        .filter(|(_, (expected, counted))| expected != counted)
+        .map(|(column, (expected, counted))| {
+            format!(
+                "{}: expected {}, counted {}",
+                column.name(),
+                expected,
+                counted
+            )
+        })
+        .collect()
+}
+
+//
+// Verify files against a manifest of counts, like `sha256sum -c` does for checksums.
+// Prints OK or FAILED for every file and fails if any of them does not match.
+// With `details`, only mismatches are printed, each with the columns that differ.
+//
+pub fn check(
+    manifest: &str,
+    opts: &ShowOptions,
+    count_opts: &CountOptions,
+    details: bool,
+) -> Result<(), Error> {
+    let content = fs::read_to_string(manifest)?;
+    let columns = opts.columns();
+    let mut failed = 0;
+    let mut malformed = 0;
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let (expected, filename) = match parse_line(line, columns.len()) {
+            Some(parsed) => parsed,
+            None => {
+                malformed += 1;
+                continue;
+            }
+        };
+        // The summary line of a previous run
+        if filename == opts.total_label() && fs::metadata(filename).is_err() {
+            continue;
+        }
+        match crate::count(filename, count_opts) {
+            Ok(m) if opts.selected(&m) == expected => {
+                if !details {
+                    println!("{}: OK", filename)
+                }
+            }
+            Ok(m) => {
+                println!("{}: FAILED", filename);
+                if details {
+                    for mismatch in mismatches(&columns, &expected, &opts.selected(&m)) {
+                        println!("  {}", mismatch);
+                    }
+                }
+                failed += 1;
+            }
+            Err(err) => {
+                println!("{}: FAILED open or read", filename);
+                if !opts.quiet {
+                    crate::errors::report_error(filename, &err);
+                }
+                failed += 1;
+            }
+        }
+    }
+    if malformed > 0 {
+        let message = crate::i18n::message("improperly-formatted", &[("count", malformed.into())]);
+        eprintln!("wc: {}", message);
+    }
+    if failed > 0 {
+        let message = crate::i18n::message("not-matched", &[("count", failed.into())]);
+        eprintln!("wc: {}", message);
+        return Err(crate::errors::reported(Error::new(
+            ErrorKind::InvalidData,
+            format!("{} counts did not match", failed),
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_manifest_line() {
+        assert_eq!(
+            parse_line("  9   7  43 tests/abc.txt", 3),
+            Some((vec![9, 7, 43], "tests/abc.txt"))
+        );
+        assert_eq!(
+            parse_line("12 a file with spaces.txt", 1),
+            Some((vec![12], "a file with spaces.txt"))
+        );
+        assert_eq!(parse_line(" 9   7  43", 3), None);
+        assert_eq!(parse_line(" 9   x  43 abc.txt", 3), None);
+    }
+
+    #[test]
+    fn describe_mismatches() {
+        assert_eq!(
+            mismatches(
+                &[Column::Lines, Column::Words, Column::Bytes],
+                &[9, 7, 43],
+                &[10, 7, 45]
+            ),
+            [
+                "lines: expected 9, counted 10",
+                "bytes: expected 43, counted 45"
+            ]
+        );
+    }
+}
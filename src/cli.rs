@@ -0,0 +1,969 @@
+//
+// The command line of wc. Values are parsed and checked here,
+// so that the options reach the rest of wc typed.
+//
+use clap::{Parser, Subcommand, ValueEnum};
+use std::io::{Error, ErrorKind};
+use std::time::Duration;
+
+// Names of the columns, e.g. for --sort
+pub const COLUMNS: &[&str] = &[
+    "lines",
+    "words",
+    "chars",
+    "bytes",
+    "graphemes",
+    "max-line-length",
+    "records",
+    "fields",
+    "min-fields",
+    "modal-fields",
+    "documents",
+    "invalid-documents",
+    "cues",
+    "messages",
+    "cells",
+    "code-lines",
+    "unique-words",
+];
+
+// What to do about input that is not valid in its encoding
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum InvalidInput {
+    // Fail counting the file
+    Strict,
+    // Count each malformed sequence as one U+FFFD replacement character
+    Replace,
+    // Count the bytes, but not as characters, like GNU wc does
+    #[default]
+    Ignore,
+}
+
+// How words are told apart
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum WordMode {
+    // Sequences of non-whitespace characters, like GNU wc
+    #[default]
+    Whitespace,
+    // Unicode word boundaries (UAX #29), punctuation is not counted as words
+    Unicode,
+}
+
+// What is counted as one character
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum CharUnit {
+    // Unicode scalar values (code points)
+    #[default]
+    Scalars,
+    // UTF-16 code units, as JavaScript, Java or many databases count string length
+    Utf16,
+    // Bytes of the UTF-8 encoded text, after decoding the input
+    Bytes,
+}
+
+// Syntax of the records counted by --records
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Dialect {
+    Csv,
+    Tsv,
+}
+
+// When to use color and bold text
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum ColorMode {
+    // On a terminal, unless NO_COLOR is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+// How problems with single files are reported on stderr
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum ErrorFormat {
+    #[default]
+    Text,
+    // One object per line with the file, a kind like not_found and the message
+    Json,
+}
+
+// Whether devices, FIFOs and sockets are read
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Devices {
+    Read,
+    Skip,
+}
+
+// A range like 10:20 of --line-range or --byte-range, either end may be left out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Range {
+    pub start: Option<u64>,
+    pub end: Option<u64>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Print a completion script for the shell to stdout
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+}
+
+#[derive(Debug, Parser)]
+#[command(name = "wc", version, author, about)]
+pub struct Cli {
+    /// Print only the byte counts.
+    #[arg(short = 'c', long, overrides_with = "chars")]
+    pub bytes: bool,
+
+    /// Print only the character counts.
+    #[arg(short = 'm', long, overrides_with = "bytes")]
+    pub chars: bool,
+
+    /// Print the character, byte and grapheme cluster (user-perceived character) counts side by
+    /// side.
+    #[arg(long)]
+    pub all_units: bool,
+
+    /// What --chars counts: Unicode scalar values, UTF-16 code units as many editors and
+    /// databases do, or bytes of the text encoded as UTF-8.
+    #[arg(long, value_name = "unit", value_enum, default_value_t)]
+    pub chars_as: CharUnit,
+
+    /// Print only the word counts. Words are separated by whitespace, or by Unicode word
+    /// boundaries with --words=unicode, which works for CJK text and does not count punctuation.
+    #[arg(
+        short,
+        long,
+        value_name = "mode",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "whitespace"
+    )]
+    pub words: Option<WordMode>,
+
+    /// Also separate words at any of the given characters, e.g. ',;' or ':'.
+    #[arg(long, value_name = "chars")]
+    pub word_delimiters: Option<String>,
+
+    /// Print only the newline counts.
+    #[arg(short, long)]
+    pub lines: bool,
+
+    /// Print only the maximum display widths. Tabs are set at every 8th column, unless
+    /// --tab-width says otherwise. Display widths of wide characters are considered.
+    /// Non-printable characters are given 0 width.
+    #[arg(short = 'L', long)]
+    pub max_line_length: bool,
+
+    /// Ignore terminal escape sequences, e.g. colors, when counting characters, words and
+    /// widths.
+    #[arg(long)]
+    pub strip_ansi: bool,
+
+    /// Set tab stops every given number of columns for the maximum display width.
+    #[arg(long, value_name = "columns", default_value_t = 8, value_parser = parse_positive)]
+    pub tab_width: usize,
+
+    /// Display width of East Asian characters of ambiguous width, like many Greek and Cyrillic
+    /// letters or box drawing characters. CJK terminals usually show them with 2 columns.
+    #[arg(long, value_name = "columns", default_value_t = 1, value_parser = parse_ambiguous_width)]
+    pub ambiguous_width: u8,
+
+    /// Group the digits of counts by thousands as usual in the numeric locale (LC_ALL,
+    /// LC_NUMERIC or LANG), e.g. 1,234,567 or 1.234.567.
+    #[arg(long)]
+    pub group_digits: bool,
+
+    /// Group the digits of counts by thousands with the given separator, regardless of the
+    /// locale.
+    #[arg(long, value_name = "separator")]
+    pub thousands_sep: Option<String>,
+
+    /// Sort the files by a comma-separated list of columns or name, e.g. lines:desc,name:asc,
+    /// each key breaking the ties of those before it. Counts are sorted largest first and
+    /// names alphabetically, unless :asc or :desc is given.
+    #[arg(long, value_name = "key[:asc|:desc],...", value_parser = parse_sort)]
+    pub sort: Option<String>,
+
+    /// Print only the first N files after sorting. The total still includes all files.
+    #[arg(long, value_name = "N", requires = "sort")]
+    pub top: Option<usize>,
+
+    /// Allow the files to be listed in the order their counts are done rather than the order
+    /// they were given or found in. wc counts one file after the other, so the two are the
+    /// same and the option changes nothing; it is accepted for scripts that pass it anyway.
+    #[arg(long)]
+    pub unordered: bool,
+
+    /// Do not list files with fewer than N lines.
+    #[arg(long, value_name = "N")]
+    pub min_lines: Option<usize>,
+
+    /// Do not list files with more than N lines.
+    #[arg(long, value_name = "N")]
+    pub max_lines: Option<usize>,
+
+    /// Do not list files with fewer than N words.
+    #[arg(long, value_name = "N")]
+    pub min_words: Option<usize>,
+
+    /// Do not list files with more than N words.
+    #[arg(long, value_name = "N")]
+    pub max_words: Option<usize>,
+
+    /// Do not list files with fewer than N characters.
+    #[arg(long, value_name = "N")]
+    pub min_chars: Option<usize>,
+
+    /// Do not list files with more than N characters.
+    #[arg(long, value_name = "N")]
+    pub max_chars: Option<usize>,
+
+    /// Do not list files with fewer than N bytes (suffixes like K or MiB are allowed).
+    #[arg(long, value_name = "N", value_parser = parse_size)]
+    pub min_bytes: Option<u64>,
+
+    /// Do not list files with more than N bytes (suffixes like K or MiB are allowed).
+    #[arg(long, value_name = "N", value_parser = parse_size)]
+    pub max_bytes: Option<u64>,
+
+    /// Leave files that are not listed because of --min-*/--max-* or --filter out of the total.
+    #[arg(long)]
+    pub filter_total: bool,
+
+    /// List only files whose name matches the regular expression. The total still includes all
+    /// files unless --filter-total is given.
+    #[arg(long, value_name = "regex", value_parser = regex::Regex::new)]
+    pub filter: Option<regex::Regex>,
+
+    /// Exit with status 2 if any file violates the condition, e.g. 'lines>500' or 'bytes>=1M'.
+    /// Prefix the column with 'total.' to check the total instead. Can be given more than once.
+    #[arg(long, value_name = "condition")]
+    pub fail_if: Vec<String>,
+
+    /// Print the file name first, followed by the counts, all separated by tabs.
+    #[arg(long)]
+    pub filename_first: bool,
+
+    /// Label the total with this instead of "total".
+    #[arg(long, value_name = "label")]
+    pub total_label: Option<String>,
+
+    /// Print the total before the files instead of after them.
+    #[arg(long)]
+    pub total_first: bool,
+
+    /// Print counts that meet the condition in red, e.g. 'lines>1000' or 'bytes>=1M'. Prefix the
+    /// column with 'total.' for the total. Can be given more than once.
+    #[arg(long, value_name = "condition")]
+    pub highlight: Vec<String>,
+
+    /// When to use color and bold text: on a terminal unless NO_COLOR is set (auto), always or
+    /// never.
+    #[arg(long, value_name = "when", value_enum, default_value_t)]
+    pub color: ColorMode,
+
+    /// Save the counts of all files as JSON, to compare later runs against with --diff.
+    #[arg(long, value_name = "file")]
+    pub save_baseline: Option<String>,
+
+    /// Keep where counting each file stopped in file and, the next time, count only what was
+    /// appended since, e.g. to append-only logs. Files that got shorter or start differently
+    /// are counted from the start.
+    #[arg(
+        long,
+        value_name = "file",
+        conflicts_with_all = ["combine", "split_by", "estimate", "filter_cmd", "detect_encoding"]
+    )]
+    pub incremental: Option<String>,
+
+    /// Print how the counts changed since the baseline saved with --save-baseline instead of
+    /// the counts.
+    #[arg(long, value_name = "baseline")]
+    pub diff: Option<String>,
+
+    /// Leave files whose counts did not change since the baseline out of --diff.
+    #[arg(long, requires = "diff")]
+    pub changed_only: bool,
+
+    /// Save the counts and a hash of the content of all files as JSON, to tell later with
+    /// --since which files were added, removed or changed.
+    #[arg(long, value_name = "file", conflicts_with_all = ["incremental", "estimate"])]
+    pub snapshot: Option<String>,
+
+    /// Print the files that were added, removed or changed since the snapshot saved with
+    /// --snapshot, with how their counts changed, instead of the counts.
+    #[arg(
+        long,
+        value_name = "snapshot",
+        conflicts_with_all = ["diff", "incremental", "estimate"]
+    )]
+    pub since: Option<String>,
+
+    /// Count two files or directories and print their counts and the differences between them.
+    #[arg(long, value_name = "path", num_args = 2)]
+    pub compare: Option<Vec<String>>,
+
+    /// Print how far each file and the total are towards a target, e.g. words=50000. Combine
+    /// with --watch to follow the progress while writing.
+    #[arg(long, value_name = "column=target")]
+    pub goal: Option<String>,
+
+    /// Print a running total of the files so far after every N files.
+    #[arg(long, value_name = "N", value_parser = parse_positive)]
+    pub subtotal_every: Option<usize>,
+
+    /// Print one line per directory with the counts of all files below it, grouping by the
+    /// first DEPTH (default 1) directories of the paths.
+    #[arg(long, value_name = "dir[:DEPTH]", value_parser = parse_group_by)]
+    pub group_by: Option<usize>,
+
+    /// Write every word of all files with the number of times it occurs to file, as JSON if
+    /// its name ends in .json, as CSV otherwise.
+    #[arg(long, value_name = "file")]
+    pub export_vocab: Option<String>,
+
+    /// Write how often each of the 256 byte values occurs in every file to file, as JSON if
+    /// its name ends in .json, as CSV otherwise.
+    #[arg(long, value_name = "file")]
+    pub byte_histogram: Option<String>,
+
+    /// Print how many times smaller each file gets when compressed with the fast LZ4
+    /// compressor, e.g. about 2 for text, about 1 for data that is compressed or encrypted.
+    #[arg(long)]
+    pub compressibility: bool,
+
+    /// Write how many lines of every file have each length to file, as CSV. Lengths are in
+    /// columns, like --max-line-length counts them.
+    #[arg(long, value_name = "file.csv")]
+    pub export_line_lengths: Option<String>,
+
+    /// Count all files as one stream, as if they were concatenated, and print a single line.
+    /// The files are read as they are, without extracting the text of documents.
+    #[arg(long)]
+    pub combine: bool,
+
+    /// Exit with status 2 and list the files that are empty or have no lines, if there are any.
+    #[arg(long)]
+    pub fail_on_empty: bool,
+
+    /// Print the number of CSV (default) or TSV records, where quoted fields may span lines,
+    /// and the most fields in a record.
+    #[arg(
+        long,
+        value_name = "format",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "csv"
+    )]
+    pub records: Option<Dialect>,
+
+    /// Print the number of records separated by the delimiter (a single byte, or \t for tab)
+    /// and the most, fewest and most common number of fields in a record. Files with records
+    /// of different numbers of fields are listed on stderr and wc exits with status 2.
+    #[arg(
+        long,
+        value_name = "delimiter",
+        value_parser = parse_delimiter,
+        conflicts_with = "records"
+    )]
+    pub fields: Option<u8>,
+
+    /// Print the number of JSON values, one per line (NDJSON) or the elements of a top-level
+    /// array, and the number of those that are not valid JSON.
+    #[arg(long, conflicts_with = "combine")]
+    pub json_docs: bool,
+
+    /// Count only the prose of Markdown: leave out front matter, code blocks, link
+    /// destinations and formatting markers. Lines are still counted as in the file.
+    #[arg(long)]
+    pub markdown: bool,
+
+    /// Count only the text of HTML: leave out tags, comments, scripts and styles, and decode
+    /// character references. Lines are still counted as in the file.
+    #[arg(long, conflicts_with = "markdown")]
+    pub html: bool,
+
+    /// Count only the prose of LaTeX: leave out the preamble, commands, math, verbatim text
+    /// and comments. Lines are still counted as in the file.
+    #[arg(long, conflicts_with_all = ["markdown", "html"])]
+    pub latex: bool,
+
+    /// Leave out YAML (---) or TOML (+++) front matter at the start of files from all counts,
+    /// including its lines and bytes.
+    #[arg(long)]
+    pub skip_front_matter: bool,
+
+    /// Count only lines START to END of each file, numbered from 1 and including END, like
+    /// sed -n 'START,ENDp'. Either may be left out to count from the first or to the last line.
+    #[arg(
+        long,
+        value_name = "START:END",
+        value_parser = parse_line_range,
+        conflicts_with_all = ["incremental", "estimate"]
+    )]
+    pub line_range: Option<Range>,
+
+    /// Leave out the lines matching the regular expression, e.g. '^\s*#', from all counts,
+    /// including their line breaks.
+    #[arg(long, value_name = "regex", value_parser = regex::Regex::new)]
+    pub ignore_lines: Option<regex::Regex>,
+
+    /// Count only the lines matching the regular expression, like grep regex | wc but with
+    /// the file names and the total. Lines that also match --ignore-lines are left out.
+    #[arg(long, value_name = "regex", value_parser = regex::Regex::new)]
+    pub match_lines: Option<regex::Regex>,
+
+    /// Count only the first N lines of each file, like head -n N.
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = ["line_range", "incremental", "estimate"]
+    )]
+    pub head: Option<u64>,
+
+    /// Count only the last N lines of each file, like tail -n N. Files are read from the
+    /// start of those lines, pipes are read to the end, keeping only the last N lines.
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = [
+            "line_range",
+            "head",
+            "byte_range",
+            "incremental",
+            "estimate",
+            "follow"
+        ]
+    )]
+    pub tail: Option<u64>,
+
+    /// Count only the bytes from offset START up to, but not including, offset END of each
+    /// file, e.g. 1G:1100M. Either may be left out to count from the start or to the end.
+    /// Files are read from START on, the bytes before it are skipped in pipes.
+    #[arg(
+        long,
+        value_name = "START:END",
+        value_parser = parse_byte_range,
+        conflicts_with_all = ["incremental", "estimate", "filter_cmd"]
+    )]
+    pub byte_range: Option<Range>,
+
+    /// Print the number of messages of mbox mail archives, and count only the message bodies:
+    /// headers are left out, including their lines.
+    #[arg(long, conflicts_with_all = ["markdown", "html", "latex"])]
+    pub mbox: bool,
+
+    /// Print the number of cells of Jupyter notebooks and the lines of their code cells. Of
+    /// .ipynb files, only the text of the markdown cells is counted otherwise.
+    #[arg(long)]
+    pub cells: bool,
+
+    /// Print the number of cues of subtitle files. Of .srt and .vtt files, only the text of
+    /// the cues is counted in any case.
+    #[arg(long)]
+    pub cues: bool,
+
+    /// Print the number of distinct words, estimated with a HyperLogLog sketch in constant
+    /// memory. The margin of error at 95% confidence, about 1.6%, follows the file name.
+    #[arg(long)]
+    pub approx_unique: bool,
+
+    /// Print the number of lines containing each of the comma-separated patterns, one column
+    /// per pattern in the given order. Defaults to ERROR,WARN,INFO,DEBUG.
+    #[arg(
+        long,
+        value_name = "patterns",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "ERROR,WARN,INFO,DEBUG"
+    )]
+    pub log_levels: Option<String>,
+
+    /// Print how many times each of the words listed in file, one per line, occurs in each
+    /// file as a whole word, one column per word in the order listed.
+    #[arg(long, value_name = "file")]
+    pub count_words: Option<String>,
+
+    /// Match the words of --count-words regardless of case.
+    #[arg(long, requires = "count_words")]
+    pub ignore_case: bool,
+
+    /// Split each file into sections at the lines matching the regular expression, e.g. '^# '
+    /// or '^CHAPTER', and print the counts of each section before those of the file.
+    #[arg(
+        long,
+        value_name = "regex",
+        value_parser = regex::Regex::new,
+        conflicts_with = "combine"
+    )]
+    pub split_by: Option<regex::Regex>,
+
+    /// Print the line number, words, characters and display width of every line instead of
+    /// the counts of each file.
+    #[arg(long)]
+    pub per_line: bool,
+
+    /// Sets the input file(s) to use.
+    #[arg(value_name = "FILE")]
+    pub files: Vec<String>,
+
+    /// Count the data read from file descriptor N, inherited from the calling program, e.g.
+    /// a socket or a pipe, instead of files.
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = parse_fd,
+        conflicts_with_all = ["files", "files_from_cmd", "git", "staged"]
+    )]
+    pub fd: Option<i32>,
+
+    /// Disallow processing files named on the command line, and instead process those named in
+    /// file file; each name being terminated by a zero byte (ASCII NUL). This is useful when
+    /// the list of file names is so long that it may exceed a command line length limitation.
+    /// In such cases, running wc via xargs is undesirable because it splits the list into
+    /// pieces and makes wc print a total for each sublist rather than for the entire list. One
+    /// way to produce a list of ASCII NUL terminated file names is with GNU find, using its
+    /// -print0 predicate. If file is ‘-’ then the ASCII NUL terminated file names are read
+    /// from standard input.
+    #[arg(long = "files0_from", value_name = "file")]
+    pub files0_from: Option<String>,
+
+    /// Count all files tracked by git in the current directory.
+    #[arg(long, conflicts_with = "files")]
+    pub git: bool,
+
+    /// Count only the files staged for commit in git.
+    #[arg(long, conflicts_with_all = ["files", "git"])]
+    pub staged: bool,
+
+    /// Count the files listed by command, one per line or separated by NUL characters.
+    #[arg(long, value_name = "command", conflicts_with_all = ["files", "git", "staged"])]
+    pub files_from_cmd: Option<String>,
+
+    /// Include hidden files and directories in the files found by --git and --staged.
+    #[arg(long)]
+    pub hidden: bool,
+
+    /// Whether to read or skip devices, FIFOs and sockets. Files found by --git and --staged
+    /// are skipped by default, files named on the command line are read.
+    #[arg(long, value_name = "action", value_enum)]
+    pub devices: Option<Devices>,
+
+    /// Estimate the lines, words and characters of large files from evenly spaced samples of
+    /// the given percentage of their content, 1 if not given. Estimated counts are marked
+    /// with their margin of error.
+    #[arg(
+        long,
+        value_name = "percent",
+        num_args = 0..=1,
+        default_missing_value = "1",
+        value_parser = parse_percent
+    )]
+    pub estimate: Option<f64>,
+
+    /// Pipe each file through the shell command, e.g. 'pandoc -t plain' or 'jq -r .text',
+    /// and count its output instead, labeled with the name of the file.
+    #[arg(
+        long,
+        value_name = "command",
+        conflicts_with_all = ["combine", "split_by", "estimate", "detect_encoding"]
+    )]
+    pub filter_cmd: Option<String>,
+
+    /// Keep the hash tables for --export-vocab, --cross-dupes and the cache of --daemon within
+    /// roughly size, e.g. 256M, by leaving out the rarest words, deciding shared lines by
+    /// their hashes alone and emptying the cache. Counts that depend on them are approximate.
+    #[arg(long, value_name = "size", value_parser = parse_size)]
+    pub max_memory: Option<u64>,
+
+    /// Skip files larger than size, e.g. 100M or 2G.
+    #[arg(long, value_name = "size", value_parser = parse_size)]
+    pub max_filesize: Option<u64>,
+
+    /// Count in the background: with the lowest CPU priority, idle I/O priority where the
+    /// system has one, and without reading ahead, so that other work is not slowed down.
+    #[arg(long)]
+    pub nice: bool,
+
+    /// Give up on a file that is not counted within this many seconds, e.g. a FIFO nobody
+    /// writes to, and report it as unreadable. Reading it stops once a pending read returns.
+    #[arg(long, value_name = "seconds", value_parser = parse_seconds, conflicts_with = "combine")]
+    pub timeout: Option<Duration>,
+
+    /// Instead of the counts, print how many lines of each file also occur in another of the
+    /// files, ignoring blank lines. With -vv, the shared lines are logged.
+    #[arg(long, conflicts_with_all = ["daemon", "check", "verify", "interactive"])]
+    pub cross_dupes: bool,
+
+    /// Only print the files that would be counted, after --git, --staged, --files-from-cmd,
+    /// wildcards and the options that skip files, without counting them.
+    #[arg(long, conflicts_with_all = ["daemon", "check", "verify", "interactive"])]
+    pub list_files: bool,
+
+    /// Listen on the Unix socket for newline-delimited paths, or ':length' followed by that
+    /// many bytes of data, and reply to each with a line of JSON counts.
+    #[arg(
+        long,
+        value_name = "socket",
+        conflicts_with_all = ["files", "files_from_cmd", "git", "staged", "watch", "follow"]
+    )]
+    pub daemon: Option<String>,
+
+    /// Read counts from manifest, a previous output of wc with the same count options, and
+    /// check that the files still have them.
+    #[arg(
+        long,
+        value_name = "manifest",
+        conflicts_with_all = ["files", "files_from_cmd", "git", "staged"]
+    )]
+    pub check: Option<String>,
+
+    /// Like --check, but print only the files that do not match, with the expected and counted
+    /// value of every differing count.
+    #[arg(
+        long,
+        value_name = "report",
+        conflicts_with_all = ["check", "files", "files_from_cmd", "git", "staged"]
+    )]
+    pub verify: Option<String>,
+
+    /// Repeatedly read text from the terminal until EOF or a line containing only '.', and
+    /// print its counts.
+    #[arg(long, conflicts_with_all = ["files", "files_from_cmd", "git", "staged"])]
+    pub interactive: bool,
+
+    /// Decode the input from the given encoding, e.g. latin1, windows-1252 or shift_jis,
+    /// before counting characters and words. Bytes are always counted as they are.
+    #[arg(long, value_name = "name", value_parser = parse_encoding)]
+    pub encoding: Option<&'static encoding_rs::Encoding>,
+
+    /// What to do with byte sequences that are invalid in the encoding: fail the file
+    /// (strict), count each as a replacement character (replace) or count them as bytes only
+    /// (ignore).
+    #[arg(long, value_name = "policy", value_enum, default_value_t)]
+    pub invalid_utf8: InvalidInput,
+
+    /// Guess the encoding of each file from its content and decode it accordingly before
+    /// counting.
+    #[arg(long, conflicts_with_all = ["encoding", "combine"])]
+    pub detect_encoding: bool,
+
+    /// Guess the encoding of each file like --detect-encoding and print it, and whether the
+    /// guess is certain, in extra columns.
+    #[arg(long, conflicts_with_all = ["encoding", "combine"])]
+    pub print_encoding: bool,
+
+    /// Keep running and re-count whenever one of the files changes.
+    #[arg(long)]
+    pub watch: bool,
+
+    /// Redraw the counts in place in watch and follow mode, instead of printing them again
+    /// below, and highlight those that changed since the previous refresh.
+    #[arg(long)]
+    pub live: bool,
+
+    /// Clear the screen before each reprint in watch mode.
+    #[arg(long, requires = "watch")]
+    pub clear: bool,
+
+    /// Keep the files open and count data appended to them, like tail -F, periodically
+    /// printing updated counts. Files that are truncated or replaced, e.g. by logrotate, are
+    /// followed further from their start, adding to the counts so far.
+    #[arg(long, conflicts_with = "watch")]
+    pub follow: bool,
+
+    /// Also print the lines and bytes per second appended to each followed file over the last
+    /// given number of seconds, 10 if not given.
+    #[arg(
+        long,
+        value_name = "seconds",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "10",
+        value_parser = parse_seconds,
+        requires = "follow"
+    )]
+    pub rate: Option<Duration>,
+
+    /// How often to check followed files for new data.
+    #[arg(long, value_name = "seconds", default_value = "1", value_parser = parse_interval)]
+    pub interval: Duration,
+
+    /// Report on stderr which files were opened, skipped, extracted or served from the cache.
+    /// Give twice to also report how each file is counted. RUST_LOG overrides the level.
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Do not print messages about single files, e.g. ones that vanished, cannot be read or are
+    /// skipped. They still make wc exit with a non-zero status.
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Print on stderr how many files were counted, skipped or could not be read, the bytes
+    /// counted, the time it took and the throughput.
+    #[arg(long)]
+    pub stats: bool,
+
+    /// Stop at the first file that cannot be read or counted. By default such files are
+    /// reported, the others counted, and wc exits with status 1 at the end.
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Report problems with single files on stderr as text, or as one JSON object per line
+    /// with the file, the kind of the problem, e.g. not_found, permission_denied or skipped,
+    /// and the message.
+    #[arg(long, value_name = "format", value_enum, default_value_t)]
+    pub errors: ErrorFormat,
+
+    /// Language of messages and labels like total, e.g. de or en. Defaults to the language of
+    /// the locale (LC_ALL, LC_MESSAGES or LANG).
+    #[arg(long, value_name = "language")]
+    pub lang: Option<String>,
+
+    /// Read default options from this TOML file instead of ~/.config/wc/config.toml. Each key
+    /// is the long name of an option, e.g. tab-width = 4 or strip-ansi = true. Options given
+    /// in WC_OPTS or variables like WC_TAB_WIDTH take precedence, and those on the command
+    /// line over both.
+    #[arg(long, value_name = "file")]
+    pub config: Option<String>,
+
+    /// Compare the output of this wc with that of the system wc on generated files, written to
+    /// dir if given, and print every difference.
+    #[arg(long, hide = true, value_name = "dir")]
+    pub self_test: Option<Option<String>>,
+
+    /// Print a man page in roff format, generated from these arguments.
+    #[arg(long, hide = true)]
+    pub generate_man: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    pub fn error_policy(&self) -> crate::ErrorPolicy {
+        if self.strict {
+            crate::ErrorPolicy::Strict
+        } else {
+            crate::ErrorPolicy::Lenient
+        }
+    }
+}
+
+// Parse a size like 512, 4K, 10MB or 2GiB into bytes.
+// Single-letter and "iB" suffixes are powers of 1024, "B" suffixes powers of 1000.
+pub fn parse_size(size: &str) -> Result<u64, Error> {
+    let invalid = || Error::new(ErrorKind::InvalidInput, format!("invalid size '{}'", size));
+    let digits = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (number, suffix) = size.split_at(digits);
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    let mut unit = suffix.chars();
+    let exponent = match unit.next().map(|c| c.to_ascii_uppercase()) {
+        None => return Ok(number),
+        Some('K') => 1,
+        Some('M') => 2,
+        Some('G') => 3,
+        Some('T') => 4,
+        Some('P') => 5,
+        Some(_) => return Err(invalid()),
+    };
+    let base: u64 = match unit.as_str() {
+        "" | "iB" => 1024,
+        "B" => 1000,
+        _ => return Err(invalid()),
+    };
+    number.checked_mul(base.pow(exponent)).ok_or_else(invalid)
+}
+
+fn parse_positive(n: &str) -> Result<usize, String> {
+    match n.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err("must be a positive number".to_owned()),
+    }
+}
+
+fn parse_ambiguous_width(columns: &str) -> Result<u8, String> {
+    match columns.parse() {
+        Ok(columns @ 1..=2) => Ok(columns),
+        _ => Err("must be 1 or 2".to_owned()),
+    }
+}
+
+fn parse_fd(fd: &str) -> Result<i32, String> {
+    match fd.parse() {
+        Ok(fd) if fd > 2 => Ok(fd),
+        _ => {
+            Err("must be a file descriptor other than standard input, output and error".to_owned())
+        }
+    }
+}
+
+// Sort keys are column names or name, each optionally followed by :asc or :desc
+fn parse_sort(keys: &str) -> Result<String, String> {
+    for key in keys.split(',') {
+        let name = key
+            .strip_suffix(":asc")
+            .or_else(|| key.strip_suffix(":desc"))
+            .unwrap_or(key);
+        if name != "name" && !COLUMNS.contains(&name) {
+            return Err(format!("unknown sort key '{}'", key));
+        }
+    }
+    Ok(keys.to_owned())
+}
+
+fn parse_percent(percent: &str) -> Result<f64, String> {
+    match percent.trim_end_matches('%').parse() {
+        Ok(percent) if percent > 0.0 && percent <= 100.0 => Ok(percent),
+        _ => Err("expected a percentage above 0 and up to 100".to_owned()),
+    }
+}
+
+// Parse dir or dir:DEPTH into the number of leading directories to group by
+fn parse_group_by(group_by: &str) -> Result<usize, String> {
+    match group_by.split_once(':') {
+        None if group_by == "dir" => Ok(1),
+        Some(("dir", depth)) => match depth.parse() {
+            Ok(depth) if depth > 0 => Ok(depth),
+            _ => Err(format!(
+                "invalid depth '{}', expected a positive number",
+                depth
+            )),
+        },
+        _ => Err("expected dir or dir:DEPTH".to_owned()),
+    }
+}
+
+// A field delimiter: a single byte, or \t or "tab" for a tab
+fn parse_delimiter(delimiter: &str) -> Result<u8, String> {
+    match delimiter {
+        "\\t" | "tab" => Ok(b'\t'),
+        _ if delimiter.len() == 1 => Ok(delimiter.as_bytes()[0]),
+        _ => Err("expected a single byte".to_owned()),
+    }
+}
+
+fn parse_encoding(label: &str) -> Result<&'static encoding_rs::Encoding, String> {
+    encoding_rs::Encoding::for_label(label.as_bytes())
+        .ok_or_else(|| format!("unknown encoding '{}'", label))
+}
+
+fn parse_range(range: &str, parse: fn(&str) -> Option<u64>) -> Result<Range, String> {
+    let bound = |bound: &str| -> Result<Option<u64>, String> {
+        if bound.is_empty() {
+            Ok(None)
+        } else {
+            parse(bound)
+                .map(Some)
+                .ok_or_else(|| format!("invalid number '{}'", bound))
+        }
+    };
+    let (start, end) = range
+        .split_once(':')
+        .ok_or_else(|| "expected START:END".to_owned())?;
+    let range = Range {
+        start: bound(start)?,
+        end: bound(end)?,
+    };
+    match range {
+        Range {
+            start: Some(start),
+            end: Some(end),
+        } if start > end => Err("START must not be after END".to_owned()),
+        _ => Ok(range),
+    }
+}
+
+// Lines are numbered from 1
+fn parse_line_range(range: &str) -> Result<Range, String> {
+    let range = parse_range(range, |line| line.parse().ok())?;
+    if range.start == Some(0) || range.end == Some(0) {
+        return Err("lines are numbered from 1".to_owned());
+    }
+    Ok(range)
+}
+
+// Offsets from 0, which may be sizes like 64M
+fn parse_byte_range(range: &str) -> Result<Range, String> {
+    parse_range(range, |offset| parse_size(offset).ok())
+}
+
+fn parse_seconds(seconds: &str) -> Result<Duration, String> {
+    let seconds = seconds
+        .parse::<f64>()
+        .map_err(|_| "expected a number of seconds".to_owned())?;
+    Duration::try_from_secs_f64(seconds).map_err(|err| err.to_string())
+}
+
+// Polling without a pause would keep a core busy
+fn parse_interval(seconds: &str) -> Result<Duration, String> {
+    match parse_seconds(seconds)? {
+        Duration::ZERO => Err("must be more than 0 seconds".to_owned()),
+        interval => Ok(interval),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes() {
+        assert_eq!(parse_size("0").unwrap(), 0);
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("4K").unwrap(), 4096);
+        assert_eq!(parse_size("4k").unwrap(), 4096);
+        assert_eq!(parse_size("10MB").unwrap(), 10_000_000);
+        assert_eq!(parse_size("2GiB").unwrap(), 2 * 1024 * 1024 * 1024);
+        assert!(parse_size("").is_err());
+        assert!(parse_size("G").is_err());
+        assert!(parse_size("1X").is_err());
+        assert!(parse_size("1Kb").is_err());
+        assert!(parse_size("99999999999P").is_err());
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(parse_group_by("dir").unwrap(), 1);
+        assert_eq!(parse_group_by("dir:2").unwrap(), 2);
+        assert!(parse_group_by("file").is_err());
+        assert!(parse_group_by("dir:0").is_err());
+        assert_eq!(parse_delimiter("\\t").unwrap(), b'\t');
+        assert!(parse_delimiter("::").is_err());
+        assert!(parse_positive("0").is_err());
+        assert_eq!(parse_ambiguous_width("2").unwrap(), 2);
+        assert!(parse_ambiguous_width("3").is_err());
+        assert!(parse_sort("lines:desc,name:asc").is_ok());
+        assert!(parse_sort("lines,size").is_err());
+        assert!(parse_sort("name:up").is_err());
+        assert_eq!(parse_seconds("0.5").unwrap(), Duration::from_millis(500));
+        assert!(parse_interval("0").is_err());
+        assert!(parse_seconds("1e30").is_err());
+        assert!(parse_seconds("-1").is_err());
+        assert_eq!(
+            parse_line_range("10:").unwrap(),
+            Range {
+                start: Some(10),
+                end: None
+            }
+        );
+        assert!(parse_line_range("0:5").is_err());
+        assert!(parse_line_range("5:2").is_err());
+        assert!(parse_line_range("5").is_err());
+        assert_eq!(
+            parse_byte_range("1K:2K").unwrap(),
+            Range {
+                start: Some(1024),
+                end: Some(2048)
+            }
+        );
+        assert!(parse_byte_range(":1X").is_err());
+    }
+}
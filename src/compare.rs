@@ -0,0 +1,119 @@
+use crate::{CountOptions, Metrics, ShowOptions};
+use std::fs;
+use std::io::{self, Error};
+use std::path::Path;
+
+// Every file below a directory, in a stable order
+fn walk(dir: &Path, files: &mut Vec<String>) -> Result<(), Error> {
+    let mut entries = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            walk(&path, files)?;
+        } else {
+            files.push(path.to_string_lossy().into_owned());
+        }
+    }
+    Ok(())
+}
+
+// The counts of a file, or the total of all files below a directory
+fn count_path(path: &str, opts: &CountOptions) -> Result<Metrics, Error> {
+    if !Path::new(path).is_dir() {
+        return crate::count(path, opts);
+    }
+    let mut files = vec![];
+    walk(Path::new(path), &mut files)?;
+    let all_metrics = files
+        .iter()
+        .map(|file| crate::count(file, opts))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (mut total, _) = crate::calculate_total_and_max_width_per_column(&all_metrics);
+    total.filename = path.to_owned();
+    Ok(total)
+}
+
+//
+// Count two files or directories and print their counts one above the other,
+// followed by the differences. Differences are printed bold on a terminal.
+//
+pub fn compare(
+    out: &mut dyn io::Write,
+    a: &str,
+    b: &str,
+    opts: &ShowOptions,
+    count_opts: &CountOptions,
+) -> Result<(), Error> {
+    let (ma, mb) = (count_path(a, count_opts)?, count_path(b, count_opts)?);
+    let columns = opts.columns();
+    let difference = crate::i18n::text("difference");
+    let rows = [
+        (
+            columns.iter().map(|c| opts.format(c.value(&ma))).collect(),
+            a,
+        ),
+        (
+            columns.iter().map(|c| opts.format(c.value(&mb))).collect(),
+            b,
+        ),
+        (
+            columns
+                .iter()
+                .map(|c| format!("{:+}", c.value(&mb) as i64 - c.value(&ma) as i64))
+                .collect::<Vec<String>>(),
+            &difference,
+        ),
+    ];
+    let mut widths = vec![0; columns.len()];
+    for (cells, _) in &rows {
+        for (width, cell) in widths.iter_mut().zip(cells) {
+            *width = std::cmp::max(*width, cell.chars().count());
+        }
+    }
+
+    let highlight = opts.color;
+    for (i, (cells, name)) in rows.iter().enumerate() {
+        for ((width, cell), column) in widths.iter().zip(cells).zip(&columns) {
+            let differs = column.value(&ma) != column.value(&mb);
+            if highlight && differs && i == rows.len() - 1 {
+                write!(out, "\x1b[1m{:>width$}\x1b[0m ", cell, width = width)?;
+            } else {
+                write!(out, "{:>width$} ", cell, width = width)?;
+            }
+        }
+        writeln!(out, "{}", name)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testfile::TempPath;
+
+    #[test]
+    fn compare_directories() {
+        let root = TempPath::dir("compare");
+        let (a, b) = (root.join("a"), root.join("b"));
+        fs::create_dir_all(a.join("sub")).unwrap();
+        fs::create_dir_all(&b).unwrap();
+        fs::write(a.join("one.txt"), "one two\n").unwrap();
+        fs::write(a.join("sub").join("two.txt"), "three\nfour\n").unwrap();
+        fs::write(b.join("one.txt"), "one two\n").unwrap();
+
+        let opts = ShowOptions {
+            lines: true,
+            words: true,
+            ..Default::default()
+        };
+        let mut writer = vec![];
+        let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+        compare(&mut writer, a, b, &opts, &CountOptions::default()).unwrap();
+        let output = String::from_utf8(writer).unwrap();
+        assert_eq!(
+            output,
+            format!(" 3  4 {}\n 1  2 {}\n-2 -2 difference\n", a, b)
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//
+// How well the content of a file compresses with the fast LZ4 compressor. Text usually
+// compresses to half its size or less, what is compressed or encrypted already hardly at all.
+//
+use lz4_flex::frame::FrameEncoder;
+use std::io::prelude::*;
+use std::io::{self, Error};
+
+// Counts what is written to it instead of keeping it
+struct Measured(usize);
+
+impl Write for Measured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub struct Compressor {
+    encoder: FrameEncoder<Measured>,
+}
+
+impl Compressor {
+    pub fn new() -> Compressor {
+        Compressor {
+            encoder: FrameEncoder::new(Measured(0)),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.encoder.write_all(data)
+    }
+
+    // Size of everything compressed
+    pub fn finish(self) -> Result<usize, Error> {
+        let Measured(size) = self.encoder.finish().map_err(Error::other)?;
+        Ok(size)
+    }
+}
+
+// The size of the content divided by its compressed size, - if there is none
+pub fn ratio(bytes: usize, compressed: Option<usize>) -> String {
+    match compressed {
+        Some(compressed) if compressed > 0 => format!("{:>6.2}", bytes as f64 / compressed as f64),
+        _ => format!("{:>6}", "-"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compressed(data: &[u8]) -> usize {
+        let mut compressor = Compressor::new();
+        for chunk in data.chunks(1000) {
+            compressor.update(chunk).unwrap();
+        }
+        compressor.finish().unwrap()
+    }
+
+    #[test]
+    fn ratios() {
+        let repeated = vec![b'a'; 100_000];
+        assert!(compressed(&repeated) < 1_000);
+        // Bytes of a linear congruential generator compress hardly at all
+        let mut state = 1u32;
+        let noise: Vec<u8> = (0..100_000)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 24) as u8
+            })
+            .collect();
+        assert!(compressed(&noise) > 99_000);
+        assert_eq!(ratio(1000, Some(400)), "  2.50");
+        assert_eq!(ratio(0, None), "     -");
+    }
+}
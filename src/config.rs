@@ -0,0 +1,151 @@
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+
+// ~/.config/wc/config.toml, or in $XDG_CONFIG_HOME if that is set
+fn default_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("wc").join("config.toml"))
+}
+
+//
+// Turn the keys of a config file into arguments, each with the long name it is for:
+// `tab-width = 4` is --tab-width=4, `strip-ansi = true` is --strip-ansi
+// and `fail-if = ["lines > 10", "words > 100"]` gives --fail-if once for each element.
+//
+fn args_from_toml(text: &str) -> Result<Vec<(String, String)>, Error> {
+    let table: toml::value::Table =
+        toml::from_str(text).map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+    let mut args = vec![];
+    for (key, value) in &table {
+        let values = match value {
+            toml::Value::Array(values) => values.iter().collect(),
+            value => vec![value],
+        };
+        for value in values {
+            let arg = match value {
+                toml::Value::Boolean(true) => format!("--{}", key),
+                toml::Value::Boolean(false) => continue,
+                toml::Value::String(value) => format!("--{}={}", key, value),
+                toml::Value::Integer(value) => format!("--{}={}", key, value),
+                toml::Value::Float(value) => format!("--{}={}", key, value),
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("unsupported value for '{}'", key),
+                    ))
+                }
+            };
+            args.push((key.clone(), arg));
+        }
+    }
+    Ok(args)
+}
+
+//
+// Default arguments from the config file given, or from the default one if it exists.
+// Each comes with the long name of its option, so that it can be left out if the
+// option is also given on the command line.
+//
+pub fn default_args(path: Option<&str>) -> Result<Vec<(String, String)>, Error> {
+    let text = match path {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|err| Error::new(err.kind(), format!("{}: {}", path, err)))?,
+        None => match default_path().map(fs::read_to_string) {
+            Some(Ok(text)) => text,
+            Some(Err(err)) if err.kind() != ErrorKind::NotFound => return Err(err),
+            _ => return Ok(vec![]),
+        },
+    };
+    args_from_toml(&text)
+}
+
+//
+// Arguments from the environment, each with the long name of its option, or "" if not known.
+// WC_OPTS holds arguments separated by whitespace, e.g. "--tab-width=4 -l",
+// and WC_<NAME> sets one of the long options, e.g. WC_TAB_WIDTH=4 or WC_STRIP_ANSI=true.
+//
+pub fn env_args(
+    vars: impl Iterator<Item = (String, String)>,
+    long_names: &[&str],
+) -> Vec<(String, String)> {
+    let mut args = vec![];
+    for (var, value) in vars {
+        if var == "WC_OPTS" {
+            for arg in value.split_whitespace() {
+                let key = arg
+                    .strip_prefix("--")
+                    .map_or("", |arg| arg.split('=').next().unwrap_or_default());
+                args.push((key.to_owned(), arg.to_owned()));
+            }
+            continue;
+        }
+        let key = match var.strip_prefix("WC_") {
+            Some(name) => name.to_ascii_lowercase().replace('_', "-"),
+            None => continue,
+        };
+        if !long_names.contains(&key.as_str()) {
+            continue;
+        }
+        match value.as_str() {
+            "true" => args.push((key.clone(), format!("--{}", key))),
+            "false" => (),
+            _ => args.push((key.clone(), format!("--{}={}", key, value))),
+        }
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_args() {
+        let config = "tab-width = 4\n\
+                      strip-ansi = true\n\
+                      combine = false\n\
+                      fail-if = [\"lines > 10\", \"words > 100\"]\n";
+        let args: Vec<String> = args_from_toml(config)
+            .unwrap()
+            .into_iter()
+            .map(|(_, arg)| arg)
+            .collect();
+        assert_eq!(
+            args,
+            [
+                "--fail-if=lines > 10",
+                "--fail-if=words > 100",
+                "--strip-ansi",
+                "--tab-width=4"
+            ]
+        );
+        assert!(args_from_toml("tab-width = {}").is_err());
+    }
+
+    #[test]
+    fn environment_args() {
+        let vars = [
+            ("WC_OPTS", "--tab-width=4  -l"),
+            ("WC_STRIP_ANSI", "true"),
+            ("WC_COMBINE", "false"),
+            ("WC_GOAL", "words=1000"),
+            ("WC_UNKNOWN", "1"),
+            ("HOME", "/home/wc"),
+        ]
+        .iter()
+        .map(|(var, value)| (var.to_string(), value.to_string()));
+        let long_names = ["tab-width", "strip-ansi", "combine", "goal", "lines"];
+        assert_eq!(
+            env_args(vars, &long_names),
+            [
+                ("tab-width".to_owned(), "--tab-width=4".to_owned()),
+                ("".to_owned(), "-l".to_owned()),
+                ("strip-ansi".to_owned(), "--strip-ansi".to_owned()),
+                ("goal".to_owned(), "--goal=words=1000".to_owned())
+            ]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use std::fs::File;
+use std::io::{self, BufRead};
+
+//
+// Standard input, read with the wide-character console API if it is a Windows console.
+// Reading a console as bytes only works for text in its code page, so typed or pasted
+// text is read as UTF-16 instead and passed on as UTF-8.
+//
+#[cfg(windows)]
+pub fn stdin() -> Box<dyn BufRead> {
+    use windows_sys::Win32::System::Console::{GetConsoleMode, GetStdHandle, STD_INPUT_HANDLE};
+
+    let mut mode = 0;
+    // SAFETY: both only query the standard input handle of this process
+    let handle = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+    if unsafe { GetConsoleMode(handle, &mut mode) } == 0 {
+        return Box::new(io::stdin().lock());
+    }
+    Box::new(io::BufReader::new(ConsoleInput {
+        handle,
+        pending: vec![],
+        surrogate: None,
+    }))
+}
+
+#[cfg(not(windows))]
+pub fn stdin() -> Box<dyn BufRead> {
+    Box::new(io::stdin().lock())
+}
+
+#[cfg(windows)]
+struct ConsoleInput {
+    handle: windows_sys::Win32::Foundation::HANDLE,
+    // UTF-8 not passed on yet
+    pending: Vec<u8>,
+    // First half of a surrogate pair split between two reads
+    surrogate: Option<u16>,
+}
+
+#[cfg(windows)]
+impl io::Read for ConsoleInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        use windows_sys::Win32::System::Console::ReadConsoleW;
+
+        while self.pending.is_empty() {
+            let mut wide = [0u16; 4096];
+            let mut read = 0;
+            // SAFETY: the buffer has room for as many characters as are asked for
+            let ok = unsafe {
+                ReadConsoleW(
+                    self.handle,
+                    wide.as_mut_ptr().cast(),
+                    wide.len() as u32,
+                    &mut read,
+                    std::ptr::null(),
+                )
+            };
+            if ok == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut units: Vec<u16> = self.surrogate.take().into_iter().collect();
+            units.extend_from_slice(&wide[..read as usize]);
+            // Nothing read, or Ctrl-Z at the start of a line, ends the input as in cmd
+            if units.first().is_none_or(|&unit| unit == 0x1a) {
+                return Ok(0);
+            }
+            if units
+                .last()
+                .is_some_and(|unit| (0xd800..0xdc00).contains(unit))
+            {
+                self.surrogate = units.pop();
+            }
+            // Unpaired surrogates are counted as replacement characters
+            self.pending = String::from_utf16_lossy(&units).into_bytes();
+        }
+        let n = buf.len().min(self.pending.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        Ok(n)
+    }
+}
+
+//
+// A file descriptor inherited from the calling program, e.g. a socket or a pipe,
+// to read from instead of a named file.
+//
+#[cfg(unix)]
+pub fn inherited(fd: i32) -> io::Result<File> {
+    use std::os::fd::FromRawFd;
+
+    // SAFETY: only asks whether the descriptor is open
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the descriptor is open and not used by anything else in wc
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+#[cfg(not(unix))]
+pub fn inherited(_fd: i32) -> io::Result<File> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "reading from a file descriptor is only supported on Unix",
+    ))
+}
@@ -0,0 +1,119 @@
+use crate::{CountOptions, Counter, Metrics};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter, Error, ErrorKind};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+
+// Counts are reused as long as size and modification time of a file stay the same
+type Cache = Arc<Mutex<HashMap<String, (u64, SystemTime, Metrics)>>>;
+
+#[derive(Serialize)]
+struct Failure<'a> {
+    filename: &'a str,
+    error: String,
+}
+
+fn count_cached(path: &str, cache: &Cache, opts: &CountOptions) -> Result<Metrics, Error> {
+    let meta = fs::metadata(path)?;
+    let modified = meta.modified()?;
+    if let Some((len, mtime, m)) = cache.lock().unwrap().get(path) {
+        if *len == meta.len() && *mtime == modified {
+            log::info!("{}: unchanged, counts from the cache", path);
+            return Ok(m.clone());
+        }
+    }
+    let m = crate::count(path, opts)?;
+    let mut cache = cache.lock().unwrap();
+    if let Some(budget) = opts.max_memory {
+        let entry_size =
+            |path: &str| path.len() + std::mem::size_of::<(u64, SystemTime, Metrics)>();
+        let size: usize = cache.keys().map(|path| entry_size(path)).sum();
+        if size + entry_size(path) > budget {
+            log::info!("cache larger than --max-memory, emptied");
+            cache.clear();
+        }
+    }
+    cache.insert(path.to_owned(), (meta.len(), modified, m.clone()));
+    Ok(m)
+}
+
+fn count_data(reader: &mut dyn BufRead, len: u64, opts: &CountOptions) -> Result<Metrics, Error> {
+    let mut counter = Counter::new("-", opts);
+    let mut data = reader.take(len);
+    let mut buffer = [0; 8192];
+    loop {
+        let n = data.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        counter.update(&buffer[..n])?;
+    }
+    if data.limit() > 0 {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "data ended early"));
+    }
+    counter.finish()
+}
+
+//
+// One connection: every line is either a path to count or ':length' announcing raw data.
+// Each request is answered with one line of JSON.
+//
+fn handle(stream: UnixStream, cache: Cache, opts: &CountOptions) -> Result<(), Error> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let request = line.trim_end_matches(&['\r', '\n'][..]);
+        if request.is_empty() {
+            continue;
+        }
+        let (filename, result) = match request.strip_prefix(':') {
+            Some(len) => match len.parse() {
+                Ok(len) => ("-", count_data(&mut reader, len, opts)),
+                Err(err) => ("-", Err(Error::new(ErrorKind::InvalidInput, err))),
+            },
+            None => (request, count_cached(request, &cache, opts)),
+        };
+        let reply = match result {
+            Ok(m) => serde_json::to_string(&m),
+            Err(err) => serde_json::to_string(&Failure {
+                filename,
+                error: err.to_string(),
+            }),
+        };
+        writeln!(writer, "{}", reply?)?;
+        writer.flush()?;
+    }
+}
+
+pub fn serve(socket: &str, opts: &CountOptions) -> Result<(), Error> {
+    // Remove a socket left behind by a previous run, but never anything else
+    if let Ok(meta) = fs::symlink_metadata(socket) {
+        if meta.file_type().is_socket() {
+            fs::remove_file(socket)?;
+        }
+    }
+    let listener = UnixListener::bind(socket)?;
+    let cache = Cache::default();
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let cache = Arc::clone(&cache);
+        let opts = opts.clone();
+        thread::spawn(move || {
+            if let Err(err) = handle(stream, cache, &opts) {
+                eprintln!("wc: {}", err);
+            }
+        });
+    }
+    Ok(())
+}
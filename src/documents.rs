@@ -0,0 +1,205 @@
+//
+// Text of document formats that are not plain text, extracted so that it can be counted.
+// PDF and office formats are only supported if their feature is enabled.
+//
+use std::ffi::OsStr;
+#[cfg(feature = "office")]
+use std::fs::File;
+#[cfg(feature = "office")]
+use std::io::Read;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+// The cells of a Jupyter notebook
+#[derive(Debug, Default, PartialEq)]
+pub struct Notebook {
+    pub cells: usize,
+    pub code_lines: usize,
+    // Text of the markdown cells, each ending with a line break
+    pub markdown: String,
+}
+
+// The text of the document, or None if the file is not in a supported document format
+pub fn extract(filename: &str) -> Result<Option<String>, Error> {
+    let extension = Path::new(filename)
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        #[cfg(feature = "pdf")]
+        Some("pdf") => pdf(filename).map(Some),
+        #[cfg(feature = "office")]
+        Some("docx") => office(filename, "word/document.xml").map(Some),
+        #[cfg(feature = "office")]
+        Some("odt") => office(filename, "content.xml").map(Some),
+        _ => Ok(None),
+    }
+}
+
+// The cells of a notebook, or None if the file is not a notebook
+pub fn notebook(filename: &str) -> Result<Option<Notebook>, Error> {
+    let extension = Path::new(filename).extension().and_then(OsStr::to_str);
+    if !extension.is_some_and(|extension| extension.eq_ignore_ascii_case("ipynb")) {
+        return Ok(None);
+    }
+    parse_notebook(&std::fs::read_to_string(filename)?).map(Some)
+}
+
+fn parse_notebook(json: &str) -> Result<Notebook, Error> {
+    let invalid = |message: &str| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("not a Jupyter notebook: {}", message),
+        )
+    };
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|err| invalid(&err.to_string()))?;
+    let cells = value
+        .get("cells")
+        .and_then(serde_json::Value::as_array)
+        .ok_or_else(|| invalid("no cells"))?;
+    let mut notebook = Notebook {
+        cells: cells.len(),
+        ..Default::default()
+    };
+    for cell in cells {
+        // The source is either a string or a list of lines that keep their line breaks
+        let source = match cell.get("source") {
+            Some(serde_json::Value::String(source)) => source.clone(),
+            Some(serde_json::Value::Array(lines)) => {
+                lines.iter().filter_map(serde_json::Value::as_str).collect()
+            }
+            _ => String::new(),
+        };
+        match cell.get("cell_type").and_then(serde_json::Value::as_str) {
+            Some("code") => notebook.code_lines += source.lines().count(),
+            Some("markdown") if !source.is_empty() => {
+                notebook.markdown.push_str(&source);
+                if !source.ends_with('\n') {
+                    notebook.markdown.push('\n');
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(notebook)
+}
+
+#[cfg(feature = "pdf")]
+fn pdf(filename: &str) -> Result<String, Error> {
+    let text = pdf_extract::extract_text(filename)
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+    if text.trim().is_empty() {
+        eprintln!(
+            "wc: {}: no text layer, the PDF probably consists of scanned images",
+            filename
+        );
+    }
+    Ok(text)
+}
+
+// Read the XML of the document text from the zip archive of an office document
+#[cfg(feature = "office")]
+fn office(filename: &str, document: &str) -> Result<String, Error> {
+    let invalid = |err| Error::new(ErrorKind::InvalidData, err);
+    let mut archive = zip::ZipArchive::new(File::open(filename)?).map_err(invalid)?;
+    let mut xml = String::new();
+    archive
+        .by_name(document)
+        .map_err(invalid)?
+        .read_to_string(&mut xml)?;
+    Ok(xml_text(&xml, document == "word/document.xml"))
+}
+
+//
+// The text of WordprocessingML (docx) or OpenDocument (odt) XML, one paragraph per line.
+// In docx, only text runs (w:t) are text; everything else is formatting or field codes.
+//
+#[cfg(feature = "office")]
+fn xml_text(xml: &str, only_text_runs: bool) -> String {
+    let mut text = String::new();
+    let mut in_text = !only_text_runs;
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        if in_text {
+            text.push_str(&crate::markup::decode_entities(&rest[..start]));
+        }
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let tag = &rest[start + 1..end];
+        let (closing, empty) = (tag.starts_with('/'), tag.ends_with('/'));
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        match name {
+            "w:t" if only_text_runs => in_text = !closing && !empty,
+            "w:p" | "text:p" | "text:h" if closing || empty => text.push('\n'),
+            "w:tab" | "text:tab" => text.push('\t'),
+            "w:br" | "w:cr" | "text:line-break" => text.push('\n'),
+            "text:s" => {
+                // A run of spaces, text:c of them
+                let count = tag
+                    .split("text:c=\"")
+                    .nth(1)
+                    .and_then(|c| c.split('"').next())
+                    .and_then(|c| c.parse().ok())
+                    .unwrap_or(1);
+                text.extend(std::iter::repeat_n(' ', count));
+            }
+            _ => (),
+        }
+        rest = &rest[end + 1..];
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notebook_cells() {
+        let json = r##"{
+            "cells": [
+                {"cell_type": "markdown", "metadata": {}, "source": ["# Analysis\n", "Load the *data*."]},
+                {"cell_type": "code", "metadata": {}, "outputs": [], "source": ["import pandas\n", "df = pandas.read_csv('a.csv')"]},
+                {"cell_type": "raw", "metadata": {}, "source": "raw text"},
+                {"cell_type": "code", "metadata": {}, "outputs": [], "source": "df.head()\n"}
+            ],
+            "metadata": {}, "nbformat": 4, "nbformat_minor": 5
+        }"##;
+        assert_eq!(
+            parse_notebook(json).unwrap(),
+            Notebook {
+                cells: 4,
+                code_lines: 3,
+                markdown: "# Analysis\nLoad the *data*.\n".to_owned()
+            }
+        );
+        assert!(parse_notebook("{}").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "office")]
+    fn docx_text() {
+        let xml = "<?xml version=\"1.0\"?><w:document><w:body>\
+                   <w:p><w:r><w:t>Fish &amp; </w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t xml:space=\"preserve\">chips</w:t></w:r></w:p>\
+                   <w:p/><w:p><w:r><w:instrText> PAGE </w:instrText><w:tab/><w:t>end</w:t></w:r></w:p>\
+                   </w:body></w:document>";
+        assert_eq!(xml_text(xml, true), "Fish & chips\n\n\tend\n");
+    }
+
+    #[test]
+    #[cfg(feature = "office")]
+    fn odt_text() {
+        let xml = "<office:document-content><office:body><office:text>\
+                   <text:h text:outline-level=\"1\">Title</text:h>\
+                   <text:p>one<text:s text:c=\"3\"/>two<text:line-break/>three</text:p>\
+                   </office:text></office:body></office:document-content>";
+        assert_eq!(xml_text(xml, false), "Title\none   two\nthree\n");
+    }
+}
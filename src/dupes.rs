@@ -0,0 +1,198 @@
+//
+// Lines that occur in more than one of the files, for --cross-dupes. A first pass puts
+// a hash of every distinct line of each file into Bloom filters to find the candidates
+// cheaply; a second pass confirms them with the lines themselves. Blank lines are ignored.
+//
+use crate::ShowOptions;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::prelude::*;
+use std::io::{self, BufReader, Error};
+
+// 1 MiB per filter, with four bits per line
+const BLOOM_BITS: usize = 1 << 23;
+const BLOOM_HASHES: u64 = 4;
+
+// What the table of candidates needs around each line, roughly
+const CANDIDATE_OVERHEAD: usize = 96;
+
+struct Bloom {
+    bits: Vec<u64>,
+}
+
+impl Bloom {
+    fn new() -> Bloom {
+        Bloom {
+            bits: vec![0; BLOOM_BITS / 64],
+        }
+    }
+
+    // Positions of the bits from one hash, by double hashing
+    fn positions(hash: u64) -> impl Iterator<Item = usize> {
+        let (a, b) = (hash & 0xffff_ffff, (hash >> 32) | 1);
+        (0..BLOOM_HASHES).map(move |i| (a.wrapping_add(i.wrapping_mul(b)) as usize) % BLOOM_BITS)
+    }
+
+    fn insert(&mut self, hash: u64) {
+        for bit in Bloom::positions(hash) {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    fn contains(&self, hash: u64) -> bool {
+        Bloom::positions(hash).all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+}
+
+fn hash(line: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    line.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Call f with every line of the file that is not blank, without its line break
+fn for_each_line(file: &str, mut f: impl FnMut(&[u8])) -> Result<(), Error> {
+    let mut reader = BufReader::new(File::open(file)?);
+    let mut line = vec![];
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        let text = line.strip_suffix(b"\n").unwrap_or(&line);
+        let text = text.strip_suffix(b"\r").unwrap_or(text);
+        if !text.iter().all(u8::is_ascii_whitespace) {
+            f(text);
+        }
+        line.clear();
+    }
+    Ok(())
+}
+
+//
+// How many lines of each file also occur in another one. If the candidates take more than
+// max_memory, the filters alone decide, and lines are counted that only seem to be shared.
+//
+fn cross_dupes(files: &[String], max_memory: Option<usize>) -> Result<Vec<usize>, Error> {
+    let (mut seen, mut repeated) = (Bloom::new(), Bloom::new());
+    for file in files {
+        let mut hashes = HashSet::new();
+        for_each_line(file, |line| {
+            hashes.insert(hash(line));
+        })?;
+        for &hash in &hashes {
+            if seen.contains(hash) {
+                repeated.insert(hash);
+            }
+        }
+        hashes.into_iter().for_each(|hash| seen.insert(hash));
+    }
+    // The files each candidate occurs in; false positives of the filters show up in one only
+    let mut candidates: HashMap<Vec<u8>, HashSet<usize>> = HashMap::new();
+    let mut size = 0;
+    let budget = max_memory.unwrap_or(usize::MAX);
+    for (i, file) in files.iter().enumerate() {
+        for_each_line(file, |line| {
+            if size <= budget && repeated.contains(hash(line)) {
+                let found_in = candidates.entry(line.to_owned()).or_insert_with(|| {
+                    size += line.len() + CANDIDATE_OVERHEAD;
+                    HashSet::new()
+                });
+                found_in.insert(i);
+            }
+        })?;
+    }
+    if size > budget {
+        eprintln!("wc: {}", crate::i18n::text("dupes-approximate"));
+        let mut counts = vec![0; files.len()];
+        for (i, file) in files.iter().enumerate() {
+            for_each_line(file, |line| {
+                if repeated.contains(hash(line)) {
+                    counts[i] += 1;
+                }
+            })?;
+        }
+        return Ok(counts);
+    }
+    candidates.retain(|line, found_in| {
+        let shared = found_in.len() > 1;
+        if shared {
+            log::debug!(
+                "in {} files: {}",
+                found_in.len(),
+                String::from_utf8_lossy(line)
+            );
+        }
+        shared
+    });
+    let mut counts = vec![0; files.len()];
+    for (i, file) in files.iter().enumerate() {
+        for_each_line(file, |line| {
+            if candidates.contains_key(line) {
+                counts[i] += 1;
+            }
+        })?;
+    }
+    Ok(counts)
+}
+
+//
+// Print the number of lines of each file that also occur in another of the files,
+// and their total.
+//
+pub fn print_cross_dupes(
+    out: &mut dyn io::Write,
+    files: &[String],
+    max_memory: Option<usize>,
+    opts: &ShowOptions,
+) -> Result<(), Error> {
+    let counts = cross_dupes(files, max_memory)?;
+    let total: usize = counts.iter().sum();
+    let width = opts.format(total).chars().count();
+    for (count, file) in counts.iter().zip(files) {
+        writeln!(
+            out,
+            "{:>width$} {}",
+            opts.format(*count),
+            file,
+            width = width
+        )?;
+    }
+    if files.len() > 1 {
+        writeln!(
+            out,
+            "{:>width$} {}",
+            opts.format(total),
+            opts.total_label(),
+            width = width
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testfile::TempPath;
+
+    #[test]
+    fn shared_lines() {
+        let dir = TempPath::dir("dupes");
+        let files: Vec<String> = [
+            "shared\nonly a\nshared\n\n",
+            "other\r\nshared\r\nalso b\n",
+            "also b\nonly c",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, content)| {
+            let path = dir.join(format!("{}.txt", i));
+            std::fs::write(&path, content).unwrap();
+            path.to_string_lossy().into_owned()
+        })
+        .collect();
+        let counts = cross_dupes(&files, None).unwrap();
+        // Without false positives of the filters, their guess is right
+        let approximate = cross_dupes(&files, Some(0)).unwrap();
+        assert_eq!(counts, [2, 2, 1]);
+        assert_eq!(approximate, counts);
+    }
+}
@@ -0,0 +1,112 @@
+//
+// Problems with single files, reported on stderr either as "wc: <file>: <message>"
+// or, with --errors=json, as one JSON object per line for tools to parse.
+//
+use serde::Serialize;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use std::sync::OnceLock;
+use wc::cli::ErrorFormat;
+
+static FORMAT: OnceLock<ErrorFormat> = OnceLock::new();
+
+pub fn init(format: ErrorFormat) {
+    let _ = FORMAT.set(format);
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    file: &'a str,
+    kind: &'a str,
+    message: &'a str,
+}
+
+fn format(format: ErrorFormat, file: &str, kind: &str, message: &str) -> String {
+    match format {
+        ErrorFormat::Text => format!("wc: {}: {}", file, message),
+        ErrorFormat::Json => serde_json::to_string(&Record {
+            file,
+            kind,
+            message,
+        })
+        .unwrap(),
+    }
+}
+
+// The kind is stable across languages, e.g. not_found or skipped, unlike the message
+pub fn report(file: &str, kind: &str, message: &str) {
+    let error_format = FORMAT.get().copied().unwrap_or_default();
+    eprintln!("{}", format(error_format, file, kind, message));
+}
+
+pub fn report_error(file: &str, err: &Error) {
+    report(file, kind(err.kind()), &err.to_string());
+}
+
+// Marks an error that was reported on stderr already, e.g. with the file it is about,
+// so that it is not reported again when wc exits.
+#[derive(Debug)]
+pub struct Reported(Error);
+
+impl fmt::Display for Reported {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for Reported {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+// The error, of the same kind, marked as reported
+pub fn reported(err: Error) -> Error {
+    Error::new(err.kind(), Reported(err))
+}
+
+pub fn is_reported(err: &Error) -> bool {
+    err.get_ref().is_some_and(|err| err.is::<Reported>())
+}
+
+// A fixed name for the kind, so that tools can rely on it whatever Debug prints
+fn kind(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::NotFound => "not_found",
+        ErrorKind::PermissionDenied => "permission_denied",
+        ErrorKind::AlreadyExists => "already_exists",
+        ErrorKind::WouldBlock => "would_block",
+        ErrorKind::InvalidInput => "invalid_input",
+        ErrorKind::InvalidData => "invalid_data",
+        ErrorKind::TimedOut => "timed_out",
+        ErrorKind::Interrupted => "interrupted",
+        ErrorKind::Unsupported => "unsupported",
+        ErrorKind::UnexpectedEof => "unexpected_eof",
+        ErrorKind::OutOfMemory => "out_of_memory",
+        ErrorKind::BrokenPipe => "broken_pipe",
+        _ => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records() {
+        assert_eq!(kind(ErrorKind::NotFound), "not_found");
+        assert_eq!(kind(ErrorKind::PermissionDenied), "permission_denied");
+        assert_eq!(kind(ErrorKind::Other), "other");
+        let err = reported(Error::from(ErrorKind::NotFound));
+        assert!(is_reported(&err) && err.kind() == ErrorKind::NotFound);
+        assert!(!is_reported(&Error::from(ErrorKind::NotFound)));
+        assert_eq!(
+            format(ErrorFormat::Text, "a.txt", "not_found", "No such file"),
+            "wc: a.txt: No such file"
+        );
+        assert_eq!(
+            format(ErrorFormat::Json, "a\"b", "not_found", "No such file"),
+            r#"{"file":"a\"b","kind":"not_found","message":"No such file"}"#
+        );
+    }
+}
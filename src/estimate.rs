@@ -0,0 +1,127 @@
+//
+// Counts of very large files extrapolated from evenly spaced blocks, for --estimate.
+// Lines, words and characters are estimated from their average density in the blocks,
+// with a margin of error at 95% confidence. Bytes are taken from the file size.
+//
+use crate::{CountOptions, Counter, Margins, Metrics};
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{Error, SeekFrom};
+
+const BLOCK_SIZE: u64 = 64 * 1024;
+// Files with fewer blocks are counted in full
+const MIN_BLOCKS: u64 = 64;
+// Even if the percentage asks for fewer, to get a useful margin of error
+const MIN_SAMPLES: u64 = 32;
+
+// Mean of the counts in the blocks and the margin of error of that mean
+fn mean_and_margin(counts: &[usize], population: u64) -> (f64, f64) {
+    let n = counts.len() as f64;
+    let mean = counts.iter().sum::<usize>() as f64 / n;
+    let variance = counts
+        .iter()
+        .map(|&count| (count as f64 - mean).powi(2))
+        .sum::<f64>()
+        / (n - 1.0);
+    // The samples are drawn without replacement from the blocks of the file
+    let correction = 1.0 - n / population as f64;
+    (mean, 1.96 * (variance / n * correction).sqrt())
+}
+
+//
+// Counts of the file estimated from samples of percent of its blocks, or None if the file
+// is too small for that to be worth it.
+//
+pub fn count(
+    file: &mut File,
+    filename: &str,
+    percent: f64,
+    opts: &CountOptions,
+) -> Result<Option<Metrics>, Error> {
+    let len = file.metadata()?.len();
+    let blocks = len / BLOCK_SIZE;
+    let samples = ((blocks as f64 * percent / 100.0).ceil() as u64).max(MIN_SAMPLES);
+    if blocks < MIN_BLOCKS || samples >= blocks {
+        return Ok(None);
+    }
+    log::info!(
+        "{}: estimated from {} of {} blocks",
+        filename,
+        samples,
+        blocks
+    );
+    let mut sampled = vec![];
+    let mut block = vec![0; BLOCK_SIZE as usize];
+    for i in 0..samples {
+        file.seek(SeekFrom::Start(i * (len - BLOCK_SIZE) / (samples - 1)))?;
+        file.read_exact(&mut block)?;
+        let mut counter = Counter::new(filename, opts);
+        counter.update(&block)?;
+        sampled.push(counter.finish()?);
+    }
+    let scale = len as f64 / BLOCK_SIZE as f64;
+    let estimate = |count: fn(&Metrics) -> usize| {
+        let counts: Vec<usize> = sampled.iter().map(count).collect();
+        let (mean, margin) = mean_and_margin(&counts, blocks);
+        (
+            (mean * scale).round() as usize,
+            (margin * scale).ceil() as usize,
+        )
+    };
+    let (lines, lines_margin) = estimate(|m| m.lines);
+    let (words, words_margin) = estimate(|m| m.words);
+    let (chars, chars_margin) = estimate(|m| m.chars);
+    Ok(Some(Metrics {
+        bytes: len as usize,
+        lines,
+        words,
+        chars,
+        // Only the longest line in the samples is known
+        max_line_length: sampled.iter().map(|m| m.max_line_length).max().unwrap_or(0),
+        filename: filename.to_owned(),
+        margins: Some(Margins {
+            lines: lines_margin,
+            words: words_margin,
+            chars: chars_margin,
+        }),
+        ..Default::default()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testfile::TempPath;
+
+    #[test]
+    fn estimate_lines() {
+        let path = TempPath::new("estimate");
+        // Lines of varying length, 11 bytes on average
+        let text: String = (0..1_000_000)
+            .map(|i| ["a\n", "bb cc\n", "dddd eeee\n", "ffff ffff ffff gggg\n"][i % 4])
+            .collect();
+        std::fs::write(&path, &text).unwrap();
+        let m = count(
+            &mut File::open(&path).unwrap(),
+            "big",
+            1.0,
+            &CountOptions::default(),
+        )
+        .unwrap()
+        .unwrap();
+        let margins = m.margins.unwrap();
+        assert_eq!(m.bytes, text.len());
+        assert!(m.lines.abs_diff(1_000_000) <= margins.lines.max(1_000));
+        assert!(m.words.abs_diff(2_250_000) <= margins.words.max(2_250));
+        assert!(m.chars.abs_diff(text.len()) <= margins.chars.max(text.len() / 1_000));
+        let small = "a\n".repeat(1000);
+        std::fs::write(&path, &small).unwrap();
+        let m = count(
+            &mut File::open(&path).unwrap(),
+            "small",
+            1.0,
+            &CountOptions::default(),
+        );
+        assert!(m.unwrap().is_none());
+    }
+}
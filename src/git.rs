@@ -0,0 +1,37 @@
+use std::io::{Error, ErrorKind};
+use std::process::Command;
+
+//
+// List files known to git in the current directory, as reported by git itself,
+// so that no shell plumbing is needed to feed them into wc.
+// With `staged`, only files added, copied, modified or renamed in the index are listed;
+// deleted files are left out because there is nothing left to count.
+//
+pub fn files(staged: bool) -> Result<Vec<String>, Error> {
+    let mut git = Command::new("git");
+    if staged {
+        git.args([
+            "diff",
+            "--cached",
+            "--name-only",
+            "--relative",
+            "--diff-filter=ACMR",
+            "-z",
+        ]);
+    } else {
+        git.args(["ls-files", "-z"]);
+    }
+    let output = git.output()?;
+    if !output.status.success() {
+        return Err(Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ));
+    }
+    let stdout =
+        String::from_utf8(output.stdout).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+    Ok(stdout
+        .split('\0')
+        .filter(|name| !name.is_empty())
+        .map(str::to_owned)
+        .collect())
+}
@@ -0,0 +1,107 @@
+use crate::vocabulary::csv_field;
+use crate::Metrics;
+use serde::Serialize;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufWriter, Error};
+
+#[derive(Serialize)]
+struct Histogram<'a> {
+    file: &'a str,
+    // How often each byte value occurs, indexed by the value
+    counts: &'a [usize],
+}
+
+fn write_csv(writer: &mut dyn Write, all_metrics: &[Metrics]) -> Result<(), Error> {
+    writeln!(writer, "file,byte,count")?;
+    for m in all_metrics {
+        let file = csv_field(&m.filename);
+        for (byte, count) in m.byte_histogram.iter().enumerate() {
+            writeln!(writer, "{},{},{}", file, byte, count)?;
+        }
+    }
+    Ok(())
+}
+
+//
+// Write how often each of the 256 byte values occurs in every file.
+// The file is JSON if its name ends in .json, CSV with one row per file and byte otherwise.
+//
+pub fn export(path: &str, all_metrics: &[Metrics]) -> Result<(), Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    if path.ends_with(".json") {
+        let histograms: Vec<Histogram> = all_metrics
+            .iter()
+            .map(|m| Histogram {
+                file: &m.filename,
+                counts: &m.byte_histogram,
+            })
+            .collect();
+        serde_json::to_writer_pretty(&mut writer, &histograms)?;
+    } else {
+        write_csv(&mut writer, all_metrics)?;
+    }
+    writer.flush()
+}
+
+fn write_line_lengths(writer: &mut dyn Write, all_metrics: &[Metrics]) -> Result<(), Error> {
+    writeln!(writer, "file,length,count")?;
+    for m in all_metrics {
+        let file = csv_field(&m.filename);
+        for (length, count) in &m.line_lengths {
+            writeln!(writer, "{},{},{}", file, length, count)?;
+        }
+    }
+    Ok(())
+}
+
+//
+// Write how many lines of every file have each length as CSV, one row per file and
+// length that occurs, shortest first.
+//
+pub fn export_line_lengths(path: &str, all_metrics: &[Metrics]) -> Result<(), Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_line_lengths(&mut writer, all_metrics)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{count_reader, CountOptions};
+
+    #[test]
+    fn byte_counts() {
+        let opts = CountOptions {
+            byte_histogram: true,
+            ..Default::default()
+        };
+        let m = count_reader(&mut &b"a,b\n\xff"[..], "x,y", &opts).unwrap();
+        assert_eq!(m.byte_histogram.len(), 256);
+        assert_eq!(m.byte_histogram[b',' as usize], 1);
+        assert_eq!(m.byte_histogram[0xff], 1);
+        let mut csv = vec![];
+        write_csv(&mut csv, &[m]).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 257);
+        assert_eq!(lines[0], "file,byte,count");
+        assert_eq!(lines[1 + b'\n' as usize], "\"x,y\",10,1");
+        assert_eq!(lines[1], "\"x,y\",0,0");
+    }
+
+    #[test]
+    fn line_lengths() {
+        let opts = CountOptions {
+            line_lengths: true,
+            ..Default::default()
+        };
+        let m = count_reader(&mut &b"ab\n\nab\r\nabcd\ta"[..], "x", &opts).unwrap();
+        let mut csv = vec![];
+        write_line_lengths(&mut csv, &[m]).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "file,length,count\nx,0,1\nx,2,2\nx,9,1\n"
+        );
+    }
+}
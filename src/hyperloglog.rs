@@ -0,0 +1,95 @@
+//
+// HyperLogLog sketch of the distinct words of a text, for --approx-unique. It takes
+// 16 KiB however many words there are, and estimates their number with a standard
+// error of 1.04 / sqrt(registers), about 0.8%. Sketches of several files can be merged.
+//
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+const PRECISION: u32 = 14;
+const REGISTERS: usize = 1 << PRECISION;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sketch {
+    registers: Vec<u8>,
+}
+
+impl Default for Sketch {
+    fn default() -> Self {
+        Sketch {
+            registers: vec![0; REGISTERS],
+        }
+    }
+}
+
+impl Sketch {
+    pub fn insert(&mut self, word: &str) {
+        // DefaultHasher::new always uses the same keys, so sketches stay comparable
+        let mut hasher = DefaultHasher::new();
+        word.hash(&mut hasher);
+        let hash = hasher.finish();
+        let register = (hash >> (64 - PRECISION)) as usize;
+        // Position of the first one bit after the register bits, counting from 1
+        let rank = ((hash << PRECISION) | (1 << (PRECISION - 1))).leading_zeros() + 1;
+        self.registers[register] = self.registers[register].max(rank as u8);
+    }
+
+    pub fn merge(&mut self, other: &Sketch) {
+        for (register, other) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(*other);
+        }
+    }
+
+    pub fn estimate(&self) -> usize {
+        let m = REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&rank| 2f64.powi(-(rank as i32)))
+            .sum();
+        let estimate = alpha * m * m / sum;
+        let empty = self.registers.iter().filter(|&&rank| rank == 0).count();
+        // Linear counting is more accurate while many registers are still empty
+        if estimate <= 2.5 * m && empty > 0 {
+            (m * (m / empty as f64).ln()).round() as usize
+        } else {
+            estimate.round() as usize
+        }
+    }
+
+    // How far off the estimate may be, at 95% confidence
+    pub fn margin(&self) -> usize {
+        (1.96 * 1.04 / (REGISTERS as f64).sqrt() * self.estimate() as f64).ceil() as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distinct_words() {
+        let mut sketch = Sketch::default();
+        assert_eq!(sketch.estimate(), 0);
+        for _ in 0..3 {
+            for i in 0..100_000 {
+                sketch.insert(&format!("word{}", i));
+            }
+        }
+        // Within the margin 95% of the time, so allow a bit more
+        assert!(sketch.estimate().abs_diff(100_000) <= 3_000);
+        let mut other = Sketch::default();
+        for i in 50_000..150_000 {
+            other.insert(&format!("word{}", i));
+        }
+        sketch.merge(&other);
+        assert!(sketch.estimate().abs_diff(150_000) <= 4_500);
+        assert!((2_000..3_000).contains(&sketch.margin()));
+        let mut few = Sketch::default();
+        ["a", "b", "a", "c"]
+            .iter()
+            .for_each(|word| few.insert(word));
+        assert_eq!(few.estimate(), 3);
+    }
+}
@@ -0,0 +1,100 @@
+//
+// Messages and labels in the language of the user. Translations are the Fluent files
+// in locales/, built into the binary. English is used for anything not translated.
+//
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use std::sync::OnceLock;
+
+const TRANSLATIONS: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+];
+
+// The translation to use first, then the English one
+static BUNDLES: OnceLock<Vec<FluentBundle<FluentResource>>> = OnceLock::new();
+
+// Use the language given, e.g. de, or that of the locale (LC_ALL, LC_MESSAGES or LANG)
+pub fn init(lang: Option<&str>) {
+    let lang = match lang {
+        Some(lang) => lang.to_owned(),
+        None => ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default(),
+    };
+    let lang = lang.split(['_', '-', '.', '@']).next().unwrap_or_default();
+    let _ = BUNDLES.set(bundles(lang));
+}
+
+fn bundles(lang: &str) -> Vec<FluentBundle<FluentResource>> {
+    let mut langs = vec![lang, "en"];
+    langs.dedup();
+    langs
+        .into_iter()
+        .filter_map(|lang| TRANSLATIONS.iter().find(|(id, _)| *id == lang))
+        .map(|(id, ftl)| {
+            let resource = FluentResource::try_new(ftl.to_string()).expect("invalid translation");
+            let mut bundle = FluentBundle::new_concurrent(vec![id.parse().unwrap()]);
+            // Isolation marks around arguments show up as garbage in many terminals
+            bundle.set_use_isolating(false);
+            bundle
+                .add_resource(resource)
+                .expect("message translated twice");
+            bundle
+        })
+        .collect()
+}
+
+fn format(
+    bundles: &[FluentBundle<FluentResource>],
+    id: &str,
+    args: &[(&str, FluentValue)],
+) -> String {
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        fluent_args.set(*name, value.clone());
+    }
+    for bundle in bundles {
+        if let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) {
+            let mut errors = vec![];
+            return bundle
+                .format_pattern(pattern, Some(&fluent_args), &mut errors)
+                .into_owned();
+        }
+    }
+    id.to_owned()
+}
+
+// The message with the given id, with its arguments filled in
+pub fn message(id: &str, args: &[(&str, FluentValue)]) -> String {
+    format(BUNDLES.get_or_init(|| bundles("en")), id, args)
+}
+
+pub fn text(id: &str) -> String {
+    message(id, &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translations() {
+        let (en, de) = (bundles("en"), bundles("de"));
+        assert_eq!(format(&en, "total", &[]), "total");
+        assert_eq!(format(&de, "total", &[]), "insgesamt");
+        assert_eq!(
+            format(&de, "improperly-formatted", &[("count", 1.into())]),
+            "WARNUNG: 1 Zeile ist falsch formatiert"
+        );
+        assert_eq!(
+            format(&en, "skipped-larger", &[("max", 1024.into())]),
+            "skipped, larger than 1024 bytes"
+        );
+        // Falls back to English, then to the id
+        assert_eq!(format(&bundles("fr"), "empty", &[]), "empty");
+        assert_eq!(format(&de, "unknown", &[]), "unknown");
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{CountOptions, Metrics};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter, Error, ErrorKind, SeekFrom};
+
+// How much of the start of a file is kept to tell whether it is still the same file
+const START_LEN: u64 = 64;
+
+// How far back the end of the last complete line is looked for at a time
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+//
+// Where counting a file stopped: the end of its last complete line, so that no word,
+// character or line is split between two runs, and the counts up to there.
+//
+#[derive(Deserialize, Serialize)]
+pub struct Resume {
+    offset: u64,
+    start: Vec<u8>,
+    metrics: Metrics,
+}
+
+// Where counting stopped the last time, nothing if there was no last time
+pub fn load(path: &str) -> Result<Vec<Resume>, Error> {
+    match File::open(path) {
+        Ok(f) => Ok(serde_json::from_reader(BufReader::new(f))?),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(vec![]),
+        Err(err) => Err(err),
+    }
+}
+
+pub fn save(path: &str, resumes: &[Resume]) -> Result<(), Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(&mut writer, resumes)?;
+    writer.flush()
+}
+
+// Offset just past the last line break between from and to, or from if there is none
+fn end_of_last_line(f: &mut File, from: u64, to: u64) -> Result<u64, Error> {
+    let mut end = to;
+    let mut chunk = vec![];
+    while end > from {
+        let start = end.saturating_sub(CHUNK_SIZE).max(from);
+        f.seek(SeekFrom::Start(start))?;
+        chunk.clear();
+        (&mut *f).take(end - start).read_to_end(&mut chunk)?;
+        if let Some(position) = chunk.iter().rposition(|&byte| byte == b'\n') {
+            return Ok(start + position as u64 + 1);
+        }
+        end = start;
+    }
+    Ok(from)
+}
+
+//
+// Count only what was appended to a file since it was counted the last time.
+// A file that got shorter or starts differently, e.g. a rotated log, is counted anew.
+// Returns the counts of the whole file and where to resume the next time.
+//
+pub fn count(
+    filename: &str,
+    previous: &[Resume],
+    opts: &CountOptions,
+) -> Result<(Metrics, Resume), Error> {
+    let mut f = File::open(filename)?;
+    let len = f.metadata()?.len();
+    let mut start = vec![];
+    (&mut f).take(START_LEN).read_to_end(&mut start)?;
+    let resumed = previous.iter().find(|resume| {
+        resume.metrics.filename == filename
+            && resume.offset <= len
+            && start.starts_with(&resume.start)
+    });
+    let (offset, before) = match resumed {
+        Some(resume) => {
+            log::info!("{}: resuming at byte {}", filename, resume.offset);
+            (resume.offset, resume.metrics.clone())
+        }
+        None => (0, Metrics::default()),
+    };
+    let end = end_of_last_line(&mut f, offset, len)?;
+    f.seek(SeekFrom::Start(offset))?;
+    let mut input = BufReader::new(f);
+    let lines = crate::count_reader(&mut input.by_ref().take(end - offset), filename, opts)?;
+    let (mut complete, _) = crate::calculate_total_and_max_width_per_column(&[before, lines]);
+    complete.filename = filename.to_owned();
+    // The incomplete last line is counted, but again the next time
+    let rest = crate::count_reader(&mut input, filename, opts)?;
+    let (mut whole, _) = crate::calculate_total_and_max_width_per_column(&[complete.clone(), rest]);
+    whole.filename = filename.to_owned();
+    start.truncate(end.min(START_LEN) as usize);
+    Ok((
+        whole,
+        Resume {
+            offset: end,
+            start,
+            metrics: complete,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testfile::TempPath;
+
+    #[test]
+    fn appended() {
+        let path = TempPath::new("incremental");
+        let filename = path.name();
+        let opts = CountOptions::default();
+        let counts = |m: &Metrics| (m.lines, m.words, m.bytes, m.max_line_length);
+
+        std::fs::write(&path, "one two\nthr").unwrap();
+        let (m, resume) = count(filename, &[], &opts).unwrap();
+        assert_eq!(counts(&m), (1, 3, 11, 7));
+        assert_eq!(resume.offset, 8);
+
+        // The word split between the runs is counted once
+        let mut f = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        f.write_all(b"ee four\nfive\n").unwrap();
+        let (m, resume) = count(filename, &[resume], &opts).unwrap();
+        assert_eq!(counts(&m), (3, 5, 24, 10));
+        assert_eq!(resume.offset, 24);
+
+        // Rotated
+        std::fs::write(&path, "six\n").unwrap();
+        let (m, _) = count(filename, &[resume], &opts).unwrap();
+        assert_eq!(counts(&m), (1, 1, 4, 3));
+    }
+}
@@ -0,0 +1,16 @@
+//
+// The parts of wc that other tools can build on, like its command line.
+//
+pub mod cli;
+
+pub use cli::Cli;
+
+// What to do when one of several files cannot be counted
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ErrorPolicy {
+    // Stop at the first file that cannot be counted
+    Strict,
+    // Report the file, count the others and fail at the end
+    #[default]
+    Lenient,
+}
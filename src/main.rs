@@ -1,7 +1,48 @@
-use clap::{crate_authors, crate_description, crate_version, App, Arg, ArgMatches};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::prelude::*;
-use std::io::{self, BufReader, Error, ErrorKind};
+use std::io::{self, BufReader, Error, ErrorKind, IsTerminal};
+use std::process::{Command, Stdio};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+use wc::cli::{
+    self, parse_size, CharUnit, Cli, ColorMode, Devices, Dialect, InvalidInput, Range, WordMode,
+};
+use wc::ErrorPolicy;
+
+mod baseline;
+mod check;
+mod compare;
+mod compress;
+mod config;
+mod console;
+#[cfg(unix)]
+mod daemon;
+mod documents;
+mod dupes;
+mod errors;
+mod estimate;
+mod git;
+mod histogram;
+mod hyperloglog;
+mod i18n;
+mod incremental;
+mod markup;
+mod pipe;
+mod priority;
+mod select;
+mod selftest;
+mod signals;
+mod stats;
+#[cfg(test)]
+mod testfile;
+mod threshold;
+mod vocabulary;
+mod watch;
+mod wordlist;
 
 //
 // wc prints one line of counts for each file, and if the file was given as an argument,
@@ -14,317 +55,3797 @@ use std::io::{self, BufReader, Error, ErrorKind};
 // However, as a GNU extension, if only one count is printed, it is guaranteed to be printed without leading spaces.
 //
 
-#[derive(Clone, Debug)]
+// Counts that a baseline, snapshot or incremental state saved by an older wc lacks are zero
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
 struct Metrics {
     bytes: usize,
     chars: usize,
     lines: usize,
     words: usize,
     max_line_length: usize,
+    graphemes: usize,
+    // Records of CSV or TSV input and the most fields in one of them
+    records: usize,
+    fields: usize,
+    // The fewest fields in a record and the number of fields most records have
+    min_fields: usize,
+    modal_fields: usize,
+    // Top-level JSON values and those that failed to parse
+    documents: usize,
+    invalid_documents: usize,
+    // Cues of subtitle files
+    cues: usize,
+    // Messages of mbox files
+    messages: usize,
+    // Cells of Jupyter notebooks and the lines of their code cells
+    cells: usize,
+    code_lines: usize,
+    // Distinct words, estimated
+    unique_words: usize,
+    // Lines matching each of the log level patterns, in the order given
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    log_levels: Vec<usize>,
+    // Occurrences of each of the words of --count-words, in the order listed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    listed_words: Vec<usize>,
     filename: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding: Option<String>,
+    // Whether the guess of the encoding is certain, if it was guessed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding_confident: Option<bool>,
+    // Size of the content compressed with LZ4, if asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compressed: Option<usize>,
+    // Hash of the content, if asked for, to tell whether it changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+    // How often each word occurs, if asked for
+    #[serde(skip)]
+    vocabulary: HashMap<String, usize>,
+    // How often each byte value occurs, if asked for
+    #[serde(skip)]
+    byte_histogram: Vec<usize>,
+    // How many lines are how wide, if asked for
+    #[serde(skip)]
+    line_lengths: BTreeMap<usize, usize>,
+    // Counts of the sections of the file, if it is split
+    #[serde(skip)]
+    sections: Vec<Metrics>,
+    // How far off the counts may be, if they were estimated with --estimate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    margins: Option<Margins>,
+    // The distinct words, to merge them for the total
+    #[serde(skip)]
+    unique: Option<hyperloglog::Sketch>,
+}
+
+// Margins of error of estimated counts, at 95% confidence
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+struct Margins {
+    lines: usize,
+    words: usize,
+    chars: usize,
+}
+
+// Digit grouping for counts, e.g. 1,234,567 or 1.234.567
+struct NumberFormat {
+    separator: String,
+    // Indian numbering: the first group has three digits, all others two (12,34,567)
+    indian: bool,
+}
+
+impl NumberFormat {
+    // Conventions of the numeric locale, as set in the environment
+    fn from_locale() -> NumberFormat {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        let locale = locale.split('.').next().unwrap_or_default();
+        let language = locale.split('_').next().unwrap_or_default();
+        let separator = match (language, locale) {
+            (_, "de_CH") | (_, "it_CH") | (_, "fr_CH") | (_, "rm_CH") => "'",
+            ("de", _)
+            | ("nl", _)
+            | ("it", _)
+            | ("es", _)
+            | ("pt", _)
+            | ("da", _)
+            | ("id", _)
+            | ("tr", _)
+            | ("el", _)
+            | ("ro", _)
+            | ("sl", _)
+            | ("hr", _)
+            | ("sr", _) => ".",
+            ("fr", _)
+            | ("ru", _)
+            | ("pl", _)
+            | ("cs", _)
+            | ("sk", _)
+            | ("sv", _)
+            | ("fi", _)
+            | ("nb", _)
+            | ("nn", _)
+            | ("no", _)
+            | ("uk", _)
+            | ("hu", _)
+            | ("bg", _)
+            | ("et", _)
+            | ("lt", _)
+            | ("lv", _) => "\u{202f}",
+            _ => ",",
+        };
+        NumberFormat {
+            separator: separator.to_owned(),
+            indian: locale.ends_with("_IN"),
+        }
+    }
+
+    // Number of separators in a number with the given number of digits
+    fn separators(&self, digits: usize) -> usize {
+        match (self.indian, digits) {
+            (_, 0..=3) => 0,
+            (true, _) => (digits - 2) / 2,
+            (false, _) => (digits - 1) / 3,
+        }
+    }
+
+    fn format(&self, n: usize) -> String {
+        let digits = n.to_string();
+        let mut grouped = String::new();
+        let mut separators = self.separators(digits.len());
+        for (i, digit) in digits.chars().enumerate() {
+            grouped.push(digit);
+            let remaining = digits.len() - i - 1;
+            let boundary = if self.indian && remaining > 3 {
+                (remaining - 3).is_multiple_of(2)
+            } else {
+                remaining > 0 && remaining.is_multiple_of(3)
+            };
+            if boundary && separators > 0 {
+                grouped.push_str(&self.separator);
+                separators -= 1;
+            }
+        }
+        grouped
+    }
+
+    // Width of a column of numbers that were this many digits wide
+    fn width(&self, digits: usize) -> usize {
+        digits + self.separators(digits) * self.separator.chars().count()
+    }
+}
+
+// One of the counts, e.g. to sort the table by
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Column {
+    Lines,
+    Words,
+    Chars,
+    Bytes,
+    Graphemes,
+    MaxLineLength,
+    Records,
+    Fields,
+    MinFields,
+    ModalFields,
+    Documents,
+    InvalidDocuments,
+    Cues,
+    Messages,
+    Cells,
+    CodeLines,
+    UniqueWords,
+}
+
+impl Column {
+    fn parse(name: &str) -> Result<Column, Error> {
+        match name {
+            "lines" => Ok(Column::Lines),
+            "words" => Ok(Column::Words),
+            "chars" => Ok(Column::Chars),
+            "bytes" => Ok(Column::Bytes),
+            "graphemes" => Ok(Column::Graphemes),
+            "max-line-length" => Ok(Column::MaxLineLength),
+            "records" => Ok(Column::Records),
+            "fields" => Ok(Column::Fields),
+            "min-fields" => Ok(Column::MinFields),
+            "modal-fields" => Ok(Column::ModalFields),
+            "documents" => Ok(Column::Documents),
+            "invalid-documents" => Ok(Column::InvalidDocuments),
+            "cues" => Ok(Column::Cues),
+            "messages" => Ok(Column::Messages),
+            "cells" => Ok(Column::Cells),
+            "code-lines" => Ok(Column::CodeLines),
+            "unique-words" => Ok(Column::UniqueWords),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("unknown column '{}'", name),
+            )),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Column::Lines => "lines",
+            Column::Words => "words",
+            Column::Chars => "chars",
+            Column::Bytes => "bytes",
+            Column::Graphemes => "graphemes",
+            Column::MaxLineLength => "max-line-length",
+            Column::Records => "records",
+            Column::Fields => "fields",
+            Column::MinFields => "min-fields",
+            Column::ModalFields => "modal-fields",
+            Column::Documents => "documents",
+            Column::InvalidDocuments => "invalid-documents",
+            Column::Cues => "cues",
+            Column::Messages => "messages",
+            Column::Cells => "cells",
+            Column::CodeLines => "code-lines",
+            Column::UniqueWords => "unique-words",
+        }
+    }
+
+    fn value(self, m: &Metrics) -> usize {
+        match self {
+            Column::Lines => m.lines,
+            Column::Words => m.words,
+            Column::Chars => m.chars,
+            Column::Bytes => m.bytes,
+            Column::Graphemes => m.graphemes,
+            Column::MaxLineLength => m.max_line_length,
+            Column::Records => m.records,
+            Column::Fields => m.fields,
+            Column::MinFields => m.min_fields,
+            Column::ModalFields => m.modal_fields,
+            Column::Documents => m.documents,
+            Column::InvalidDocuments => m.invalid_documents,
+            Column::Cues => m.cues,
+            Column::Messages => m.messages,
+            Column::Cells => m.cells,
+            Column::CodeLines => m.code_lines,
+            Column::UniqueWords => m.unique_words,
+        }
+    }
+}
+
+// One key of --sort, like lines:desc
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct SortKey {
+    // The file name if there is none
+    column: Option<Column>,
+    descending: bool,
+}
+
+impl SortKey {
+    fn parse_list(keys: &str) -> Result<Vec<SortKey>, Error> {
+        keys.split(',')
+            .map(|key| {
+                let (name, direction) = match key.split_once(':') {
+                    Some((name, direction)) => (name, Some(direction)),
+                    None => (key, None),
+                };
+                let column = match name {
+                    "name" => None,
+                    _ => Some(Column::parse(name)?),
+                };
+                let descending = match direction {
+                    None => column.is_some(),
+                    Some("desc") => true,
+                    Some("asc") => false,
+                    Some(direction) => {
+                        return Err(Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("unknown sort direction '{}'", direction),
+                        ))
+                    }
+                };
+                Ok(SortKey { column, descending })
+            })
+            .collect()
+    }
+
+    fn compare(&self, a: &Metrics, b: &Metrics) -> std::cmp::Ordering {
+        let ordering = match self.column {
+            Some(column) => column.value(a).cmp(&column.value(b)),
+            None => a.filename.cmp(&b.filename),
+        };
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
 }
+
+#[derive(Default)]
 struct ShowOptions {
     lines: bool,
     chars: bool,
     bytes: bool,
     words: bool,
     max_line_length: bool,
+    graphemes: bool,
+    records: bool,
+    // Also the fewest and the most common number of fields
+    field_counts: bool,
+    documents: bool,
+    cues: bool,
+    unique_words: bool,
+    messages: bool,
+    cells: bool,
+    log_levels: bool,
+    listed_words: bool,
+    // The guessed encoding and how certain the guess is
+    encoding: bool,
+    // The size divided by the compressed size
+    compressibility: bool,
+    grouping: Option<NumberFormat>,
+    sort: Vec<SortKey>,
+    top: Option<usize>,
+    // Only files with counts in these ranges are listed
+    ranges: Vec<(Column, std::ops::RangeInclusive<usize>)>,
+    // Only files with a matching name are listed
+    filter: Option<regex::Regex>,
+    filter_total: bool,
+    // Progress towards a target count, printed as a percentage
+    goal: Option<(Column, usize)>,
+    subtotal_every: Option<usize>,
+    // Number of leading directories to aggregate the counts of files by
+    group_by: Option<usize>,
+    // Problems with single files are not reported on stderr
+    quiet: bool,
+    // Counting was interrupted, the total covers only part of the input
+    partial: bool,
+    // Use escape sequences for color and bold text
+    color: bool,
+    // Counts meeting any of these are printed in color
+    highlight: Vec<threshold::Condition>,
+    // Print the file name, then the counts, separated by tabs
+    filename_first: bool,
+    // Instead of the translation of "total"
+    total_label: Option<String>,
+    total_first: bool,
+    // The counts printed before, those that changed since are printed in color
+    changed_since: Option<Vec<Metrics>>,
 }
 
 impl ShowOptions {
-    fn from_clap_matches(opts: &ArgMatches) -> ShowOptions {
-        ShowOptions {
-            lines: opts.is_present("lines"),
-            words: opts.is_present("words"),
-            chars: opts.is_present("chars"),
-            bytes: opts.is_present("bytes"),
-            max_line_length: opts.is_present("max_line_length"),
-        }
+    fn from_cli(cli: &Cli) -> Result<ShowOptions, Error> {
+        Ok(ShowOptions {
+            lines: cli.lines,
+            words: cli.words.is_some(),
+            chars: cli.chars || cli.all_units,
+            bytes: cli.bytes || cli.all_units,
+            max_line_length: cli.max_line_length,
+            graphemes: cli.all_units,
+            records: cli.records.is_some() || cli.fields.is_some(),
+            field_counts: cli.fields.is_some(),
+            documents: cli.json_docs,
+            cues: cli.cues,
+            unique_words: cli.approx_unique,
+            messages: cli.mbox,
+            cells: cli.cells,
+            log_levels: cli.log_levels.is_some(),
+            listed_words: cli.count_words.is_some(),
+            encoding: cli.print_encoding,
+            compressibility: cli.compressibility,
+            grouping: match &cli.thousands_sep {
+                Some(separator) => Some(NumberFormat {
+                    separator: separator.clone(),
+                    indian: false,
+                }),
+                None if cli.group_digits => Some(NumberFormat::from_locale()),
+                None => None,
+            },
+            sort: cli
+                .sort
+                .as_deref()
+                .map(SortKey::parse_list)
+                .transpose()?
+                .unwrap_or_default(),
+            top: cli.top,
+            ranges: ShowOptions::ranges_from_cli(cli),
+            filter: cli.filter.clone(),
+            filter_total: cli.filter_total,
+            goal: cli.goal.as_deref().map(parse_goal).transpose()?,
+            subtotal_every: cli.subtotal_every,
+            group_by: cli.group_by,
+            quiet: cli.quiet,
+            partial: false,
+            color: match cli.color {
+                ColorMode::Always => true,
+                ColorMode::Never => false,
+                ColorMode::Auto => {
+                    io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+                }
+            },
+            highlight: cli
+                .highlight
+                .iter()
+                .map(|rule| threshold::Condition::parse(rule))
+                .collect::<Result<_, _>>()?,
+            filename_first: cli.filename_first,
+            total_label: cli.total_label.clone(),
+            total_first: cli.total_first,
+            changed_since: None,
+        })
     }
 
-    fn is_default(&self) -> bool {
-        !(self.chars || self.words || self.bytes || self.max_line_length || self.lines)
+    fn ranges_from_cli(cli: &Cli) -> Vec<(Column, std::ops::RangeInclusive<usize>)> {
+        let bytes = |size: Option<u64>| size.map(|size| size as usize);
+        let mut ranges = vec![];
+        for (min, max, column) in [
+            (cli.min_lines, cli.max_lines, Column::Lines),
+            (cli.min_words, cli.max_words, Column::Words),
+            (cli.min_chars, cli.max_chars, Column::Chars),
+            (bytes(cli.min_bytes), bytes(cli.max_bytes), Column::Bytes),
+        ] {
+            if min.is_some() || max.is_some() {
+                ranges.push((column, min.unwrap_or(0)..=max.unwrap_or(usize::MAX)));
+            }
+        }
+        ranges
     }
-}
 
+    fn total_label(&self) -> String {
+        self.total_label
+            .clone()
+            .unwrap_or_else(|| i18n::text("total"))
+    }
 
-// TODO: missing bytes from BOM?
-fn count(filename: &str) -> Result<Metrics, Error> {
-    let mut m = Metrics {
-        bytes: 0,
-        chars: 0,
-        lines: 0,
-        words: 0,
-        max_line_length: 0,
-        filename: filename.to_owned(),
-    };
+    // Whether the table shows, sorts, filters or highlights by no other than these columns
+    fn uses_only(&self, columns: &[Column]) -> bool {
+        let mut used = self.columns();
+        used.extend(self.sort.iter().filter_map(|key| key.column));
+        used.extend(self.goal.map(|(column, _)| column));
+        used.extend(self.ranges.iter().map(|(column, _)| *column));
+        used.extend(self.highlight.iter().map(|rule| rule.column()));
+        used.iter().all(|column| columns.contains(column))
+    }
 
-    let f = File::open(filename)?;
-    let mut reader = BufReader::with_capacity(1024, f);
-    let mut line_len = 0;
-    loop {
-        let buffer = reader.fill_buf()?;
-        let mut last_char_was_word_separator  = None;
-        let mut bytes = 0;
-        if buffer.is_empty() {
-            break;
+    // Columns of a file, or with is_total of the total, to highlight
+    fn highlighted(&self, m: &Metrics, is_total: bool) -> Vec<Column> {
+        if !self.color {
+            return vec![];
         }
-        let s =
-            std::str::from_utf8(&buffer).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
-        for c in s.chars() {
-            bytes += c.len_utf8();
-            m.chars += 1;
-            line_len += 1;
-            if c == '\n' {
-                m.lines += 1;
-                m.max_line_length = std::cmp::max(m.max_line_length, line_len);
-                line_len = 0;
-                break;
-            } else if c.is_whitespace() {
-                last_char_was_word_separator = Some(true);
-                if c == '\t' {
-                    line_len += 7;
-                } 
+        let mut columns: Vec<Column> = self
+            .highlight
+            .iter()
+            .filter(|rule| rule.is_met_by(m, is_total))
+            .map(|rule| rule.column())
+            .collect();
+        if let Some(previous) = &self.changed_since {
+            let before = if is_total {
+                Some(calculate_total_and_max_width_per_column(previous).0)
             } else {
-                if let Some(true) = last_char_was_word_separator {
-                    m.words += 1;
-                }
-                last_char_was_word_separator = Some(false);
+                previous.iter().find(|p| p.filename == m.filename).cloned()
+            };
+            if let Some(before) = before.filter(|_| !previous.is_empty()) {
+                columns.extend(
+                    self.columns()
+                        .into_iter()
+                        .filter(|column| column.value(m) != column.value(&before)),
+                );
             }
         }
-        if let Some(false) = last_char_was_word_separator {
-            m.words += 1;
+        columns
+    }
+
+    fn is_listed(&self, m: &Metrics) -> bool {
+        self.ranges
+            .iter()
+            .all(|(column, range)| range.contains(&column.value(m)))
+            && self
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.is_match(&m.filename))
+    }
+
+    fn format(&self, n: usize) -> String {
+        match &self.grouping {
+            Some(grouping) => grouping.format(n),
+            None => n.to_string(),
         }
-        m.bytes += bytes;
-        reader.consume(bytes);
+    }
 
-        // TODO m.max_line_length = std::cmp::max(m.max_line_length, count);
+    fn is_default(&self) -> bool {
+        !(self.chars
+            || self.words
+            || self.bytes
+            || self.max_line_length
+            || self.lines
+            || self.graphemes
+            || self.records
+            || self.documents
+            || self.cues
+            || self.unique_words
+            || self.messages
+            || self.cells)
+    }
 
+    // The columns that are shown, in the order they are printed
+    fn columns(&self) -> Vec<Column> {
+        let mut columns = vec![];
+        if self.is_default() || self.lines {
+            columns.push(Column::Lines);
+        }
+        if self.is_default() || self.words {
+            columns.push(Column::Words);
+        }
+        if self.chars {
+            columns.push(Column::Chars);
+        }
+        if self.is_default() || self.bytes {
+            columns.push(Column::Bytes);
+        }
+        if self.graphemes {
+            columns.push(Column::Graphemes);
+        }
+        if self.max_line_length {
+            columns.push(Column::MaxLineLength);
+        }
+        if self.records {
+            columns.push(Column::Records);
+            columns.push(Column::Fields);
+        }
+        if self.field_counts {
+            columns.push(Column::MinFields);
+            columns.push(Column::ModalFields);
+        }
+        if self.documents {
+            columns.push(Column::Documents);
+            columns.push(Column::InvalidDocuments);
+        }
+        if self.cues {
+            columns.push(Column::Cues);
+        }
+        if self.unique_words {
+            columns.push(Column::UniqueWords);
+        }
+        if self.messages {
+            columns.push(Column::Messages);
+        }
+        if self.cells {
+            columns.push(Column::Cells);
+            columns.push(Column::CodeLines);
+        }
+        columns
     }
 
-    Ok(m)
+    // The counts that are shown, in the order they are printed
+    fn selected(&self, m: &Metrics) -> Vec<usize> {
+        self.columns()
+            .iter()
+            .map(|column| column.value(m))
+            .collect()
+    }
 }
 
-fn print_metrics(out: &mut dyn io::Write,  m: &Metrics, opts: &ShowOptions, mwpc: &Metrics) {
-    let mut remove_column =
-    if opts.is_default() || opts.lines {
-        write!(out, "{:>width$} ", m.lines, width = mwpc.lines).unwrap();
-        1
-    } else {
-        0
-    };
-    if opts.is_default() || opts.words {
-        write!(out, "{:>width$} ", m.words, width = mwpc.words-remove_column).unwrap();
-        remove_column = 1;
-    }
-    if opts.chars {
-        write!(out, "{:>width$} ", m.chars, width = mwpc.chars-remove_column).unwrap();
-        remove_column = 1;
+
+// Kind of delimited input to count records of
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RecordFormat {
+    Csv,
+    Tsv,
+    // Quoted fields like CSV, separated by the given byte
+    Delimited(u8),
+}
+
+// Options that influence how input is counted, as opposed to how counts are shown
+#[derive(Clone)]
+struct CountOptions {
+    encoding: Option<&'static encoding_rs::Encoding>,
+    detect_encoding: bool,
+    invalid: InvalidInput,
+    words: WordMode,
+    // Characters separating words in addition to whitespace
+    word_delimiters: Vec<char>,
+    // Display East Asian ambiguous width characters with two columns, as CJK terminals do
+    ambiguous_wide: bool,
+    strip_ansi: bool,
+    tab_width: usize,
+    chars_as: CharUnit,
+    graphemes: bool,
+    vocabulary: bool,
+    byte_histogram: bool,
+    line_lengths: bool,
+    // Keep the counts of every line, not only those of the whole input
+    per_line: bool,
+    compressibility: bool,
+    hash: bool,
+    unique_words: bool,
+    records: Option<RecordFormat>,
+    json_documents: bool,
+    markup: Option<markup::Format>,
+    skip_front_matter: bool,
+    // Only these lines and bytes are counted
+    line_range: Option<Range>,
+    byte_range: Option<Range>,
+    // Only this many lines at the end are counted
+    tail: Option<u64>,
+    // Only lines matching the one, and not the other, are counted
+    match_lines: Option<regex::Regex>,
+    ignore_lines: Option<regex::Regex>,
+    // Lines containing any of these are tallied
+    log_levels: Vec<String>,
+    // Words whose occurrences are counted
+    word_list: Option<wordlist::WordList>,
+    on_error: ErrorPolicy,
+    // Give up on a file that takes longer than this
+    timeout: Option<std::time::Duration>,
+    // Keep the system from reading ahead of what is counted
+    no_readahead: bool,
+    // Estimate the counts of large files from this percentage of their content
+    estimate: Option<f64>,
+    // Count the output of this shell command, given each file as its input
+    filter_cmd: Option<String>,
+    // Bytes the hash tables may take, roughly
+    max_memory: Option<usize>,
+    // Only these counts are needed, which do not need the text to be decoded
+    raw: Option<RawCounts>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RawCounts {
+    Bytes,
+    LinesAndBytes,
+}
+
+impl Default for CountOptions {
+    fn default() -> Self {
+        CountOptions {
+            encoding: None,
+            detect_encoding: false,
+            invalid: InvalidInput::default(),
+            words: WordMode::default(),
+            word_delimiters: vec![],
+            ambiguous_wide: false,
+            strip_ansi: false,
+            tab_width: 8,
+            chars_as: CharUnit::default(),
+            graphemes: false,
+            vocabulary: false,
+            byte_histogram: false,
+            line_lengths: false,
+            per_line: false,
+            compressibility: false,
+            hash: false,
+            unique_words: false,
+            records: None,
+            json_documents: false,
+            markup: None,
+            skip_front_matter: false,
+            line_range: None,
+            byte_range: None,
+            tail: None,
+            match_lines: None,
+            ignore_lines: None,
+            log_levels: vec![],
+            word_list: None,
+            on_error: ErrorPolicy::default(),
+            timeout: None,
+            no_readahead: false,
+            estimate: None,
+            filter_cmd: None,
+            max_memory: None,
+            raw: None,
+        }
     }
-    if opts.is_default() || opts.bytes {
-        write!(out, "{:>width$} ", m.bytes, width = mwpc.bytes-remove_column).unwrap();
-        remove_column = 1;
+}
+
+impl CountOptions {
+    fn from_cli(cli: &Cli) -> Result<CountOptions, Error> {
+        Ok(CountOptions {
+            encoding: cli.encoding,
+            detect_encoding: cli.detect_encoding || cli.print_encoding,
+            invalid: cli.invalid_utf8,
+            words: cli.words.unwrap_or_default(),
+            word_delimiters: cli
+                .word_delimiters
+                .as_deref()
+                .map(|delimiters| delimiters.chars().collect())
+                .unwrap_or_default(),
+            ambiguous_wide: cli.ambiguous_width == 2,
+            strip_ansi: cli.strip_ansi,
+            tab_width: cli.tab_width,
+            chars_as: cli.chars_as,
+            graphemes: cli.all_units,
+            vocabulary: cli.export_vocab.is_some(),
+            byte_histogram: cli.byte_histogram.is_some(),
+            line_lengths: cli.export_line_lengths.is_some(),
+            per_line: cli.per_line,
+            compressibility: cli.compressibility,
+            hash: cli.snapshot.is_some() || cli.since.is_some(),
+            unique_words: cli.approx_unique,
+            records: match (cli.records, cli.fields) {
+                (_, Some(delimiter)) => Some(RecordFormat::Delimited(delimiter)),
+                (Some(Dialect::Tsv), _) => Some(RecordFormat::Tsv),
+                (Some(Dialect::Csv), _) => Some(RecordFormat::Csv),
+                (None, None) => None,
+            },
+            json_documents: cli.json_docs,
+            markup: if cli.markdown {
+                Some(markup::Format::Markdown)
+            } else if cli.html {
+                Some(markup::Format::Html)
+            } else if cli.latex {
+                Some(markup::Format::Latex)
+            } else if cli.mbox {
+                Some(markup::Format::Mbox)
+            } else {
+                None
+            },
+            skip_front_matter: cli.skip_front_matter,
+            line_range: cli.line_range.or(cli.head.map(|count| Range {
+                start: None,
+                end: Some(count),
+            })),
+            byte_range: cli.byte_range,
+            tail: cli.tail,
+            match_lines: cli.match_lines.clone(),
+            ignore_lines: cli.ignore_lines.clone(),
+            log_levels: cli
+                .log_levels
+                .as_deref()
+                .map(|patterns| patterns.split(',').map(str::to_owned).collect())
+                .unwrap_or_default(),
+            word_list: cli
+                .count_words
+                .as_deref()
+                .map(|path| wordlist::WordList::load(path, cli.ignore_case))
+                .transpose()?,
+            on_error: cli.error_policy(),
+            timeout: cli.timeout,
+            no_readahead: cli.nice,
+            estimate: cli.estimate,
+            filter_cmd: cli.filter_cmd.clone(),
+            max_memory: cli.max_memory.map(|size| size as usize),
+            raw: None,
+        })
     }
-    if opts.max_line_length {
-        write!(out, 
-            "{:>width$} ",
-            m.max_line_length,
-            width = mwpc.max_line_length - remove_column
-        ).unwrap();
+
+    // Whether counting needs more than the raw bytes, e.g. to decode or parse them
+    fn needs_text(&self) -> bool {
+        self.encoding.is_some()
+            || self.detect_encoding
+            || self.invalid == InvalidInput::Strict
+            || self.strip_ansi
+            || self.vocabulary
+            || self.byte_histogram
+            || self.line_lengths
+            || self.compressibility
+            || self.hash
+            || self.unique_words
+            || self.records.is_some()
+            || self.json_documents
+            || self.markup.is_some()
+            || self.skip_front_matter
+            || self.line_range.is_some()
+            || self.byte_range.is_some()
+            || self.tail.is_some()
+            || self.match_lines.is_some()
+            || self.ignore_lines.is_some()
+            || !self.log_levels.is_empty()
+            || self.word_list.is_some()
+            || self.estimate.is_some()
     }
-    writeln!(out, "{}", m.filename).unwrap();
 }
 
-fn calculate_total_and_max_width_per_column(ms: &[Metrics]) -> (Metrics, Metrics) {
-    let mut total = Metrics {
-        bytes: 0,
-        chars: 0,
-        lines: 0,
-        words: 0,
-        max_line_length: 0,
-        filename: "total".to_owned(),
-    };
-    let mut mwpc = Metrics {
-        bytes: 0,
-        chars: 0,
-        lines: 0,
-        words: 0,
-        max_line_length: 0,
-        filename: "".to_owned(), // Width of filename is not important
-    };
-    for m_x in ms {
-        total.bytes += m_x.bytes;
-        total.chars += m_x.chars;
-        total.lines += m_x.lines;
-        total.words += m_x.words;
-        total.max_line_length = std::cmp::max(total.max_line_length, m_x.max_line_length);
-        mwpc.bytes = std::cmp::max(mwpc.bytes, m_x.bytes);
-        mwpc.chars = std::cmp::max(mwpc.chars, m_x.chars);
-        mwpc.lines = std::cmp::max(mwpc.lines, m_x.lines);
-        mwpc.words = std::cmp::max(mwpc.words, m_x.words);
-        // mwpc.max_line_length not needed again
-    }
-    mwpc.bytes = std::cmp::max(mwpc.bytes.to_string().len(), 8);
-    mwpc.chars = std::cmp::max(mwpc.chars.to_string().len(), 8);
-    mwpc.lines = std::cmp::max(mwpc.lines.to_string().len(), 8);
-    mwpc.words = std::cmp::max(mwpc.words.to_string().len(), 8);
-    mwpc.max_line_length = std::cmp::max(total.max_line_length.to_string().len(), 8);
-    (total, mwpc)
+// Where we are in a terminal escape sequence (ECMA-48)
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Ansi {
+    Text,
+    Escape,
+    // ESC followed by intermediate bytes, e.g. ESC ( B
+    EscapeIntermediate,
+    // Control sequence, e.g. ESC [ 1 ; 31 m
+    Csi,
+    // Operating system command, e.g. ESC ] 0 ; title BEL
+    Osc,
+    OscEscape,
 }
 
-fn print_count(mut out: &mut dyn io::Write, matches: &ArgMatches) -> Result<(), Error> {
-    let opts = ShowOptions::from_clap_matches(matches);
-    if let Some(files) = matches.values_of("files") {
-        let mut all_metrics = vec![];
-        for file in files {
-            let m = count(&file)?;
-            all_metrics.push(m);
-        }
-        let (total, mwpc) = calculate_total_and_max_width_per_column(&all_metrics);
-        for m in &all_metrics {
-            print_metrics(&mut out, &m, &opts, &mwpc);
-        }
-        if all_metrics.len() > 1 {
-            print_metrics(&mut out, &total, &opts, &mwpc);
-        }
-    } else {
-        // Stdin
+impl Ansi {
+    // Advance by one character, returns whether it belongs to an escape sequence
+    fn skip(&mut self, c: char) -> bool {
+        let (next, skip) = match (*self, c) {
+            (Ansi::Text, '\x1b') => (Ansi::Escape, true),
+            (Ansi::Text, '\u{9b}') => (Ansi::Csi, true),
+            (Ansi::Text, _) => (Ansi::Text, false),
+            (Ansi::Escape, '[') => (Ansi::Csi, true),
+            (Ansi::Escape, ']') => (Ansi::Osc, true),
+            (Ansi::Escape, '\x20'..='\x2f') | (Ansi::EscapeIntermediate, '\x20'..='\x2f') => {
+                (Ansi::EscapeIntermediate, true)
+            }
+            (Ansi::Escape, _) | (Ansi::EscapeIntermediate, _) => (Ansi::Text, true),
+            (Ansi::Csi, '\x20'..='\x3f') => (Ansi::Csi, true),
+            (Ansi::Csi, _) => (Ansi::Text, true),
+            (Ansi::Osc, '\x07') => (Ansi::Text, true),
+            (Ansi::Osc, '\x1b') => (Ansi::OscEscape, true),
+            (Ansi::Osc, _) => (Ansi::Osc, true),
+            (Ansi::OscEscape, '\\') => (Ansi::Text, true),
+            (Ansi::OscEscape, _) => (Ansi::Osc, true),
+        };
+        *self = next;
+        skip
     }
+}
 
-    Ok(())
+const ZERO_WIDTH_JOINER: char = '\u{200d}';
+
+// Approximation of the Extended_Pictographic property, the characters emoji sequences are built from
+fn is_pictographic(c: char) -> bool {
+    matches!(c,
+        '\u{a9}' | '\u{ae}' | '\u{203c}' | '\u{2049}' | '\u{2122}' | '\u{2139}'
+        | '\u{2194}'..='\u{21aa}'
+        | '\u{2300}'..='\u{23ff}'
+        | '\u{24c2}'
+        | '\u{25aa}'..='\u{25fe}'
+        | '\u{2600}'..='\u{27bf}'
+        | '\u{2934}' | '\u{2935}'
+        | '\u{2b05}'..='\u{2b55}'
+        | '\u{3030}' | '\u{303d}' | '\u{3297}' | '\u{3299}'
+        | '\u{1f000}'..='\u{1faff}')
 }
 
-// TODO: read from stdin if no files are given
-// TODO: files0_from
-fn main() {
-    let matches = App::new("wc")
-        .version(crate_version!())
-        .author(crate_authors!())
-        .about(crate_description!())
-        .arg(
-            Arg::with_name("bytes")
-                .short('c')
-                .long("bytes")
-                .overrides_with("chars")
-                .help("Print only the byte counts.")
-        )
-        .arg(
-            Arg::with_name("chars")
-                .short('m')
-                .long("chars")
-                .overrides_with("bytes")
-                .help("Print only the character counts.")
-        )
-        .arg(
-            Arg::with_name("words")
-                .short('w')
-                .long("words")
-                .help("Print only the word counts.")
-        )
-        .arg(
-            Arg::with_name("lines")
-                .short('l')
-                .long("lines")
-                .help("Print only the newline counts.")
-        )
-        .arg(
-            Arg::with_name("max_line_length")
-                .short('L')
-                .long("max-line-length")
-                .help("Print only the maximum display widths. Tabs are set at every 8th column. Display widths of wide characters are considered. Non-printable characters are given 0 width.")
-        )
-        .arg(
-            Arg::with_name("files")
-                .value_name("FILE")
-                .takes_value(true)
-                .multiple(true)
-                .help("Sets the input file(s) to use.")
-        )
-        .arg(
-            Arg::with_name("files0_from")
-                .long("files0_from")
-                .value_name("file")
-                .takes_value(true)
-                .multiple(false)
-                .help("Disallow processing files named on the command line, and instead process those named in file file; each name being terminated by a zero byte (ASCII NUL). This is useful when the list of file names is so long that it may exceed a command line length limitation. In such cases, running wc via xargs is undesirable because it splits the list into pieces and makes wc print a total for each sublist rather than for the entire list. One way to produce a list of ASCII NUL terminated file names is with GNU find, using its -print0 predicate. If file is ‘-’ then the ASCII NUL terminated file names are read from standard input.")
-    )
-    .get_matches();
-
-    std::process::exit(match print_count(&mut io::stdout().lock(), &matches) {
-        Err(_) => 1,
-        Ok(_) => 0,
-    });
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::*;
+//
+// Incremental counting state, so input can be fed in arbitrary chunks
+// (e.g. as data is appended to a file that is followed).
+// Multibyte characters split across chunk boundaries are kept in `pending`
+// until the rest of their bytes arrive.
+// The encoding is UTF-8 unless the input starts with a UTF-16 byte order mark
+// or another encoding is given explicitly, which is then decoded by encoding_rs.
+//
+struct Counter {
+    metrics: Metrics,
+    opts: CountOptions,
+    line_len: usize,
+    // Widest the current line has been so far, as a carriage return starts it over
+    line_width: usize,
+    // Roughly the memory of the vocabulary, and whether words had to be left out of it
+    vocabulary_size: usize,
+    vocabulary_pruned: bool,
+    compressor: Option<compress::Compressor>,
+    hasher: Option<xxhash_rust::xxh3::Xxh3>,
+    in_word: bool,
+    // Current line, if words or grapheme clusters are found by Unicode segmentation
+    line: String,
+    // Current word, if the vocabulary is collected
+    word: String,
+    ansi: Ansi,
+    previous: Option<char>,
+    pending: Vec<u8>,
+    encoding: Option<Encoding>,
+    decoder: Option<encoding_rs::Decoder>,
+    // Parser for CSV or TSV records and the fields of the current record so far
+    records: Option<csv_core::Reader>,
+    record_fields: usize,
+    // How many records have each number of fields
+    field_counts: HashMap<usize, usize>,
+    // JSON input not parsed yet, and whether it is a single array rather than one value per line
+    json: Option<Vec<u8>>,
+    json_array: Option<bool>,
+    // Markup is removed a line at a time
+    markup: Option<markup::Filter>,
+    markup_line: String,
+    // Input is held back here until the front matter is over
+    front_matter: Option<markup::FrontMatter>,
+    // The bytes, and of them the lines, that are counted
+    byte_range: Option<select::ByteRange>,
+    line_range: Option<select::LineRange>,
+    line_filter: Option<select::LineFilter>,
+    // Input is held back here until it is over, to count only its last lines
+    tail: Option<select::Tail>,
+    // Current line, if log levels or listed words are tallied
+    log_line: String,
+    // Counts of the lines that ended, and the words and characters before the current line
+    line_counts: Option<Vec<LineCounts>>,
+    words_before_line: usize,
+    chars_before_line: usize,
+}
 
-    fn sample_metrics() -> Vec<Metrics> {
-        let m0 = Metrics {
-            bytes: 123,
-            chars: 9_876_543_210,
-            lines: 789,
-            words: 1_239_875_670,
-            max_line_length: 456,
-            filename: "m0".to_owned(),
+// Counts of a single line, without its line break
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct LineCounts {
+    words: usize,
+    chars: usize,
+    width: usize,
+}
+
+impl Counter {
+    fn new(filename: &str, opts: &CountOptions) -> Counter {
+        Counter {
+            metrics: Metrics {
+                log_levels: vec![0; opts.log_levels.len()],
+                filename: filename.to_owned(),
+                byte_histogram: if opts.byte_histogram {
+                    vec![0; 256]
+                } else {
+                    vec![]
+                },
+                listed_words: vec![0; opts.word_list.as_ref().map_or(0, wordlist::WordList::len)],
+                unique: if opts.unique_words {
+                    Some(hyperloglog::Sketch::default())
+                } else {
+                    None
+                },
+                ..Default::default()
+            },
+            opts: opts.clone(),
+            line_len: 0,
+            line_width: 0,
+            vocabulary_size: 0,
+            vocabulary_pruned: false,
+            compressor: if opts.compressibility {
+                Some(compress::Compressor::new())
+            } else {
+                None
+            },
+            hasher: if opts.hash {
+                Some(xxhash_rust::xxh3::Xxh3::new())
+            } else {
+                None
+            },
+            in_word: false,
+            line: String::new(),
+            word: String::new(),
+            ansi: Ansi::Text,
+            previous: None,
+            pending: vec![],
+            encoding: None,
+            decoder: opts.encoding.map(|encoding| encoding.new_decoder()),
+            records: opts.records.map(|format| match format {
+                RecordFormat::Csv => csv_core::Reader::new(),
+                RecordFormat::Tsv => csv_core::ReaderBuilder::new()
+                    .delimiter(b'\t')
+                    .quoting(false)
+                    .build(),
+                RecordFormat::Delimited(delimiter) => {
+                    csv_core::ReaderBuilder::new().delimiter(delimiter).build()
+                }
+            }),
+            record_fields: 0,
+            field_counts: HashMap::new(),
+            json: if opts.json_documents {
+                Some(vec![])
+            } else {
+                None
+            },
+            json_array: None,
+            markup: opts.markup.map(markup::Filter::new),
+            markup_line: String::new(),
+            front_matter: if opts.skip_front_matter {
+                Some(markup::FrontMatter::default())
+            } else {
+                None
+            },
+            byte_range: opts.byte_range.map(select::ByteRange::new),
+            line_range: opts.line_range.map(select::LineRange::new),
+            line_filter: if opts.match_lines.is_some() || opts.ignore_lines.is_some() {
+                Some(select::LineFilter::new(
+                    opts.match_lines.clone(),
+                    opts.ignore_lines.clone(),
+                ))
+            } else {
+                None
+            },
+            tail: opts.tail.map(select::Tail::new),
+            log_line: String::new(),
+            line_counts: if opts.per_line { Some(vec![]) } else { None },
+            words_before_line: 0,
+            chars_before_line: 0,
+        }
+    }
+
+    fn update(&mut self, buffer: &[u8]) -> Result<(), Error> {
+        let buffer = match &mut self.byte_range {
+            Some(byte_range) => byte_range.select(buffer),
+            None => buffer,
         };
-        let m1 = Metrics {
-            bytes: 1_234_567_890,
-            chars: 1_234_567,
-            lines: 12_345_678_901,
-            words: 4_567_890,
-            max_line_length: 4_567_890_123,
-            filename: "m1".to_owned(),
+        let selected;
+        let buffer = match &mut self.line_range {
+            Some(line_range) => {
+                selected = line_range.select(buffer);
+                &selected[..]
+            }
+            None => buffer,
         };
-        vec![m0, m1]
+        let filtered;
+        let buffer = match &mut self.line_filter {
+            Some(line_filter) => {
+                filtered = line_filter.select(buffer);
+                &filtered[..]
+            }
+            None => buffer,
+        };
+        self.update_filtered(buffer)
     }
 
-    #[test]
-    fn wider_columns() {
-        let metrics = sample_metrics();
-        let m0 = metrics[0].clone();
-        let m1 = metrics[1].clone();
-        let (total, mwpc) = calculate_total_and_max_width_per_column(&[m0.clone(), m1.clone()]);
-        assert_eq!(total.bytes, m0.bytes + m1.bytes);
-        assert_eq!(total.chars, m0.chars + m1.chars);
-        assert_eq!(total.lines, m0.lines + m1.lines);
-        assert_eq!(total.words, m0.words + m1.words);
-        assert_eq!(
-            total.max_line_length,
-            std::cmp::max(m0.max_line_length, m1.max_line_length)
-        );
-        assert_eq!(mwpc.bytes, 10);
-        assert_eq!(mwpc.chars, 10);
-        assert_eq!(mwpc.lines, 11);
-        assert_eq!(mwpc.words, 10);
-        //assert_eq!(mwpc.max_line_length, 10);
+    fn update_filtered(&mut self, buffer: &[u8]) -> Result<(), Error> {
+        match &mut self.tail {
+            Some(tail) => {
+                tail.select(buffer);
+                Ok(())
+            }
+            None => self.update_selected(buffer),
+        }
     }
 
-    #[test]
-    fn print_wide_columns() {
-        let metrics = sample_metrics();
-        let m0 = metrics[0].clone();
-        let m1 = metrics[1].clone();
-        let mwpc = Metrics {
-            bytes: 10,
-            chars: 10,
-            lines: 11,
-            words: 10,
-            max_line_length: 10,
-            filename: "m1".to_owned(),
+    // Whether the rest of the input would not be counted anyway
+    fn is_done(&self) -> bool {
+        self.byte_range
+            .as_ref()
+            .is_some_and(select::ByteRange::is_done)
+            || self
+                .line_range
+                .as_ref()
+                .is_some_and(select::LineRange::is_done)
+    }
+
+    fn update_selected(&mut self, buffer: &[u8]) -> Result<(), Error> {
+        if let Some(front_matter) = &mut self.front_matter {
+            let rest = front_matter.filter(buffer);
+            if front_matter.is_done() {
+                self.front_matter = None;
+            }
+            return if rest.is_empty() {
+                Ok(())
+            } else {
+                self.update_selected(&rest)
+            };
+        }
+        self.metrics.bytes += buffer.len();
+        for &byte in buffer {
+            if let Some(count) = self.metrics.byte_histogram.get_mut(byte as usize) {
+                *count += 1;
+            }
+        }
+        if let Some(compressor) = &mut self.compressor {
+            compressor.update(buffer)?;
+        }
+        if let Some(hasher) = &mut self.hasher {
+            hasher.update(buffer);
+        }
+        if !buffer.is_empty() {
+            self.count_records(buffer);
+            self.count_documents(buffer);
+        }
+        if self.decoder.is_some() {
+            return self.decode_with_decoder(buffer, false);
+        }
+        let joined;
+        let mut data = if self.pending.is_empty() {
+            buffer
+        } else {
+            joined = [self.pending.as_slice(), buffer].concat();
+            self.pending.clear();
+            &joined[..]
         };
-        let opts = ShowOptions {
-            lines: true,
-            chars: true,
-            bytes: false,
-            words: true,
-            max_line_length: true,
+        let encoding = match self.encoding {
+            Some(encoding) => encoding,
+            None if data.len() < 2 => {
+                self.pending.extend_from_slice(data);
+                return Ok(());
+            }
+            None => {
+                let encoding = match data[..2] {
+                    [0xff, 0xfe] => Encoding::Utf16Le,
+                    [0xfe, 0xff] => Encoding::Utf16Be,
+                    _ => Encoding::Utf8,
+                };
+                if encoding != Encoding::Utf8 {
+                    // The byte order mark is not part of the text
+                    data = &data[2..];
+                }
+                self.encoding = Some(encoding);
+                encoding
+            }
         };
-        let mut writer = vec![];
-        print_metrics(&mut writer, &m0, &opts, &mwpc);
-        print_metrics(&mut writer, &m1, &opts, &mwpc);
-        let output = std::str::from_utf8(writer.as_ref()).unwrap();
-        assert_eq!(dbg!(output), "        789 1239875670 9876543210       456 m0\n12345678901   4567890   1234567 4567890123 m1\n")
+        match encoding {
+            Encoding::Utf8 => self.decode_utf8(data),
+            Encoding::Utf16Le => self.decode_utf16(data, u16::from_le_bytes),
+            Encoding::Utf16Be => self.decode_utf16(data, u16::from_be_bytes),
+        }
+    }
+
+    // Records are parsed from the raw bytes; quoted fields may contain line breaks.
+    // An empty buffer marks the end of the input.
+    fn count_records(&mut self, mut input: &[u8]) {
+        let reader = match &mut self.records {
+            Some(reader) => reader,
+            None => return,
+        };
+        // Only the number of fields is of interest, not their content
+        let mut output = [0; 4096];
+        let mut ends = [0; 64];
+        loop {
+            let (result, read, _, fields) = reader.read_record(input, &mut output, &mut ends);
+            input = &input[read..];
+            self.record_fields += fields;
+            match result {
+                csv_core::ReadRecordResult::Record => {
+                    self.metrics.records += 1;
+                    self.metrics.fields = std::cmp::max(self.metrics.fields, self.record_fields);
+                    self.metrics.min_fields = if self.metrics.records == 1 {
+                        self.record_fields
+                    } else {
+                        std::cmp::min(self.metrics.min_fields, self.record_fields)
+                    };
+                    *self.field_counts.entry(self.record_fields).or_default() += 1;
+                    self.record_fields = 0;
+                    // Empty input would be taken as the end
+                    if input.is_empty() && read > 0 {
+                        return;
+                    }
+                }
+                csv_core::ReadRecordResult::OutputFull
+                | csv_core::ReadRecordResult::OutputEndsFull => (),
+                csv_core::ReadRecordResult::InputEmpty | csv_core::ReadRecordResult::End => return,
+            }
+        }
+    }
+
+    // JSON values are parsed line by line (NDJSON), unless the input is an array.
+    // Arrays are parsed as a whole at the end and each element counts as a value.
+    fn count_documents(&mut self, input: &[u8]) {
+        let mut json = match self.json.take() {
+            Some(json) => json,
+            None => return,
+        };
+        json.extend_from_slice(input);
+        if self.json_array.is_none() {
+            self.json_array = json
+                .iter()
+                .find(|b| !b.is_ascii_whitespace())
+                .map(|b| *b == b'[');
+        }
+        if self.json_array == Some(false) {
+            let mut start = 0;
+            while let Some(end) = json[start..].iter().position(|b| *b == b'\n') {
+                self.parse_document(&json[start..start + end]);
+                start += end + 1;
+            }
+            json.drain(..start);
+        }
+        self.json = Some(json);
+    }
+
+    fn parse_document(&mut self, json: &[u8]) {
+        if json.iter().all(u8::is_ascii_whitespace) {
+            return;
+        }
+        match serde_json::from_slice::<serde_json::Value>(json) {
+            Ok(_) => self.metrics.documents += 1,
+            Err(_) => self.metrics.invalid_documents += 1,
+        }
+    }
+
+    fn finish_documents(&mut self) {
+        let json = match self.json.take() {
+            Some(json) => json,
+            None => return,
+        };
+        if self.json_array == Some(true) {
+            match serde_json::from_slice::<Vec<serde_json::Value>>(&json) {
+                Ok(values) => self.metrics.documents += values.len(),
+                Err(_) => self.metrics.invalid_documents += 1,
+            }
+        } else {
+            self.parse_document(&json);
+        }
+    }
+
+    // Handle a malformed sequence according to the chosen policy
+    fn invalid<E>(&mut self, err: E) -> Result<(), Error>
+    where
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        match self.opts.invalid {
+            InvalidInput::Strict => Err(Error::new(ErrorKind::InvalidData, err)),
+            InvalidInput::Replace => {
+                self.push_char(char::REPLACEMENT_CHARACTER);
+                Ok(())
+            }
+            InvalidInput::Ignore => Ok(()),
+        }
+    }
+
+    fn decode_utf8(&mut self, mut data: &[u8]) -> Result<(), Error> {
+        loop {
+            let err = match std::str::from_utf8(data) {
+                Ok(s) => {
+                    s.chars().for_each(|c| self.push_char(c));
+                    return Ok(());
+                }
+                Err(err) => err,
+            };
+            let (valid, rest) = data.split_at(err.valid_up_to());
+            std::str::from_utf8(valid)
+                .unwrap()
+                .chars()
+                .for_each(|c| self.push_char(c));
+            match err.error_len() {
+                None => {
+                    // Incomplete character at the end of the chunk
+                    self.pending.extend_from_slice(rest);
+                    return Ok(());
+                }
+                Some(len) => {
+                    self.invalid(err)?;
+                    data = &rest[len..];
+                }
+            }
+        }
+    }
+
+    fn decode_with_decoder(&mut self, mut data: &[u8], last: bool) -> Result<(), Error> {
+        let mut text = String::new();
+        loop {
+            let decoder = match self.decoder.as_mut() {
+                Some(decoder) => decoder,
+                None => return Ok(()),
+            };
+            text.clear();
+            text.reserve(
+                decoder
+                    .max_utf8_buffer_length_without_replacement(data.len())
+                    .unwrap_or(data.len() * 3 + 16),
+            );
+            let (result, read) =
+                decoder.decode_to_string_without_replacement(data, &mut text, last);
+            data = &data[read..];
+            text.chars().for_each(|c| self.push_char(c));
+            match result {
+                encoding_rs::DecoderResult::InputEmpty => return Ok(()),
+                encoding_rs::DecoderResult::OutputFull => (),
+                encoding_rs::DecoderResult::Malformed(_, _) => {
+                    self.invalid("invalid byte sequence")?
+                }
+            }
+        }
+    }
+
+    fn decode_utf16(&mut self, data: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Result<(), Error> {
+        let mut units: Vec<u16> = data
+            .chunks_exact(2)
+            .map(|pair| from_bytes([pair[0], pair[1]]))
+            .collect();
+        let mut incomplete = data.len() % 2;
+        if let Some(0xd800..=0xdbff) = units.last() {
+            // High surrogate, its partner is still to come
+            units.pop();
+            incomplete += 2;
+        }
+        self.pending
+            .extend_from_slice(&data[data.len() - incomplete..]);
+        for c in char::decode_utf16(units) {
+            match c {
+                Ok(c) => self.push_char(c),
+                Err(err) => self.invalid(err)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn push_char(&mut self, c: char) {
+        if self.opts.strip_ansi && self.ansi.skip(c) {
+            return;
+        }
+        match self.markup {
+            Some(_) if c == '\n' => {
+                if self.flush_markup() {
+                    self.count_char(c);
+                }
+            }
+            Some(_) => self.markup_line.push(c),
+            None => self.count_char(c),
+        }
+    }
+
+    // Count the text of the current line; false if the line is left out entirely
+    fn flush_markup(&mut self) -> bool {
+        if let Some(filter) = &mut self.markup {
+            let text = filter.line(&self.markup_line);
+            self.metrics.cues = filter.cues();
+            self.metrics.messages = filter.messages();
+            self.markup_line.clear();
+            match text {
+                Some(text) => text.chars().for_each(|c| self.count_char(c)),
+                None => return false,
+            }
+        }
+        true
+    }
+
+    fn count_char(&mut self, c: char) {
+        let (width, after_cr) = (self.line_width, self.previous == Some('\r'));
+        self.metrics.chars += match self.opts.chars_as {
+            CharUnit::Scalars => 1,
+            CharUnit::Utf16 => c.len_utf16(),
+            CharUnit::Bytes => c.len_utf8(),
+        };
+        if !self.opts.log_levels.is_empty() || self.opts.word_list.is_some() {
+            if c == '\n' {
+                self.tally_line();
+            } else {
+                self.log_line.push(c);
+            }
+        }
+        match c {
+            '\n' => {
+                self.metrics.lines += 1;
+                self.end_line();
+                self.line_len = 0;
+            }
+            '\r' | '\x0c' => self.line_len = 0,
+            '\t' => self.line_len += self.opts.tab_width - self.line_len % self.opts.tab_width,
+            c if c.is_control() => (),
+            c => self.line_len += self.cluster_width(c),
+        }
+        self.previous = Some(c);
+        self.metrics.max_line_length = std::cmp::max(self.metrics.max_line_length, self.line_len);
+        self.line_width = std::cmp::max(self.line_width, self.line_len);
+        let delimiter = self.opts.word_delimiters.contains(&c);
+        if self.opts.words == WordMode::Whitespace {
+            if c.is_whitespace() || delimiter {
+                self.in_word = false;
+                self.end_word();
+            } else {
+                if !self.in_word {
+                    self.in_word = true;
+                    self.metrics.words += 1;
+                }
+                if self.opts.vocabulary || self.opts.unique_words {
+                    self.word.push(c);
+                }
+            }
+        }
+        // Neither word nor grapheme cluster boundaries span a line break (except CR LF),
+        // so segmenting line by line is exact
+        if self.opts.words == WordMode::Unicode || self.opts.graphemes {
+            if c == '\n' {
+                self.segment_line(true);
+            } else if delimiter && self.opts.words == WordMode::Unicode {
+                self.line.push(' ');
+            } else {
+                self.line.push(c);
+            }
+        }
+        if c == '\n' {
+            // Like the line feed, a carriage return before it is one unit in any case
+            self.count_line(width, if after_cr { 2 } else { 1 });
+        }
+    }
+
+    // Keep the counts of the line that just ended, less those of its line break
+    fn count_line(&mut self, width: usize, line_break: usize) {
+        if let Some(line_counts) = &mut self.line_counts {
+            line_counts.push(LineCounts {
+                words: self.metrics.words - self.words_before_line,
+                chars: self.metrics.chars - self.chars_before_line - line_break,
+                width,
+            });
+            self.words_before_line = self.metrics.words;
+            self.chars_before_line = self.metrics.chars;
+        }
+    }
+
+    // The counts of the lines that ended since the last call
+    fn take_line_counts(&mut self) -> Vec<LineCounts> {
+        self.line_counts
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    // Display width according to UAX #11; zero for combining and other non-printable characters
+    fn width(&self, c: char) -> usize {
+        let width = if self.opts.ambiguous_wide {
+            UnicodeWidthChar::width_cjk(c)
+        } else {
+            UnicodeWidthChar::width(c)
+        };
+        width.unwrap_or(0)
+    }
+
+    // Additional width of c on the current line.
+    // Emoji sequences are shown as one two-column glyph by terminals,
+    // so joined emoji, presentation selectors and skin tones add nothing on their own.
+    fn cluster_width(&self, c: char) -> usize {
+        let previous = self.previous.unwrap_or('\n');
+        match c {
+            ZERO_WIDTH_JOINER | '\u{fe0e}' => 0,
+            // Emoji presentation of a character that is narrow as text, e.g. U+2764 U+FE0F
+            '\u{fe0f}' if is_pictographic(previous) && self.width(previous) == 1 => 1,
+            '\u{fe0f}' => 0,
+            '\u{1f3fb}'..='\u{1f3ff}' if is_pictographic(previous) => 0,
+            c if previous == ZERO_WIDTH_JOINER && is_pictographic(c) => 0,
+            c => self.width(c),
+        }
+    }
+
+    // Count the log levels and listed words in the line that just ended
+    fn tally_line(&mut self) {
+        if let Some(word_list) = &self.opts.word_list {
+            word_list.count(&self.log_line, &mut self.metrics.listed_words);
+        }
+        for (pattern, count) in self
+            .opts
+            .log_levels
+            .iter()
+            .zip(&mut self.metrics.log_levels)
+        {
+            if self.log_line.contains(pattern.as_str()) {
+                *count += 1;
+            }
+        }
+        self.log_line.clear();
+    }
+
+    fn end_word(&mut self) {
+        if !self.word.is_empty() {
+            let word = std::mem::take(&mut self.word);
+            self.add_word(word);
+        }
+    }
+
+    // Account for the width of a line that just ended
+    fn end_line(&mut self) {
+        if self.opts.line_lengths {
+            *self
+                .metrics
+                .line_lengths
+                .entry(self.line_width)
+                .or_default() += 1;
+        }
+        self.line_width = 0;
+    }
+
+    fn add_word(&mut self, word: String) {
+        if let Some(unique) = &mut self.metrics.unique {
+            unique.insert(&word);
+        }
+        if self.opts.vocabulary {
+            let size = vocabulary::entry_size(&word);
+            let count = self.metrics.vocabulary.entry(word).or_default();
+            if *count == 0 {
+                self.vocabulary_size += size;
+            }
+            *count += 1;
+            match self.opts.max_memory {
+                Some(budget) if self.vocabulary_size > budget => {
+                    self.vocabulary_size = vocabulary::prune(&mut self.metrics.vocabulary, budget);
+                    if !self.vocabulary_pruned {
+                        let message = i18n::text("vocabulary-pruned");
+                        eprintln!("wc: {}: {}", self.metrics.filename, message);
+                        self.vocabulary_pruned = true;
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn segment_line(&mut self, newline: bool) {
+        if self.opts.words == WordMode::Unicode {
+            self.metrics.words += self.line.unicode_words().count();
+            if self.opts.vocabulary || self.opts.unique_words {
+                let words: Vec<String> = self.line.unicode_words().map(str::to_owned).collect();
+                for word in words {
+                    self.add_word(word);
+                }
+            }
+        }
+        if self.opts.graphemes {
+            self.metrics.graphemes += self.line.graphemes(true).count();
+            // A newline is a cluster on its own, unless it follows a carriage return
+            if newline && !self.line.ends_with('\r') {
+                self.metrics.graphemes += 1;
+            }
+        }
+        self.line.clear();
+    }
+
+    // Counts so far; bytes of an incomplete trailing character are included.
+    fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    fn finish(mut self) -> Result<Metrics, Error> {
+        self.end_input()?;
+        Ok(self.metrics)
+    }
+
+    // Count what was held back until the input is over
+    fn end_input(&mut self) -> Result<(), Error> {
+        if let Some(line_filter) = self.line_filter.take() {
+            self.update_filtered(&line_filter.finish())?;
+        }
+        if let Some(tail) = self.tail.take() {
+            self.update_selected(&tail.finish())?;
+        }
+        if let Some(front_matter) = self.front_matter.take() {
+            self.update_selected(&front_matter.finish())?;
+        }
+        if self.decoder.is_some() {
+            self.decode_with_decoder(&[], true)?;
+        } else if self.encoding.is_none() {
+            // Too short for a byte order mark
+            self.encoding = Some(Encoding::Utf8);
+            let pending = std::mem::take(&mut self.pending);
+            self.decode_utf8(&pending)?;
+        }
+        if !self.pending.is_empty() {
+            self.invalid("incomplete character at end of input")?;
+        }
+        if !self.markup_line.is_empty() {
+            self.flush_markup();
+        }
+        self.segment_line(false);
+        self.end_word();
+        // A last line without a line break
+        if self.previous.is_some_and(|c| c != '\n') {
+            self.count_line(self.line_width, 0);
+            self.end_line();
+        }
+        if !self.log_line.is_empty() {
+            self.tally_line();
+        }
+        self.count_records(&[]);
+        // Of equally frequent numbers of fields, the smallest
+        self.metrics.modal_fields = self
+            .field_counts
+            .iter()
+            .max_by_key(|(fields, records)| (**records, std::cmp::Reverse(**fields)))
+            .map_or(0, |(fields, _)| *fields);
+        self.finish_documents();
+        if let Some(unique) = &self.metrics.unique {
+            self.metrics.unique_words = unique.estimate();
+        }
+        if let Some(compressor) = self.compressor.take() {
+            self.metrics.compressed = Some(compressor.finish()?);
+        }
+        if let Some(hasher) = self.hasher.take() {
+            self.metrics.hash = Some(format!("{:016x}", hasher.digest()));
+        }
+        Ok(())
+    }
+}
+
+fn count_reader(
+    reader: &mut dyn BufRead,
+    filename: &str,
+    opts: &CountOptions,
+) -> Result<Metrics, Error> {
+    let mut counter = Counter::new(filename, opts);
+    feed(&mut counter, reader)?;
+    counter.finish()
+}
+
+fn feed(counter: &mut Counter, reader: &mut dyn BufRead) -> Result<(), Error> {
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() || signals::interrupted() || counter.is_done() {
+            return Ok(());
+        }
+        let len = buffer.len();
+        counter.update(buffer)?;
+        reader.consume(len);
+        signals::read(len);
+    }
+}
+
+// How much of a file chardetng gets to see to guess its encoding
+const DETECTION_SAMPLE_SIZE: u64 = 64 * 1024;
+
+// Guess the encoding from the start of the input, and whether the guess is certain.
+// Returns what was read for guessing, so it can still be counted.
+fn detect_encoding(
+    reader: &mut dyn Read,
+) -> Result<(&'static encoding_rs::Encoding, bool, Vec<u8>), Error> {
+    let mut sample = vec![];
+    reader
+        .take(DETECTION_SAMPLE_SIZE)
+        .read_to_end(&mut sample)?;
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(&sample, (sample.len() as u64) < DETECTION_SAMPLE_SIZE);
+    let (encoding, confident) = detector.guess_assess(None, true);
+    Ok((encoding, confident, sample))
+}
+
+// TODO: missing bytes from BOM?
+fn count(filename: &str, opts: &CountOptions) -> Result<Metrics, Error> {
+    if let Some(command) = &opts.filter_cmd {
+        return count_filtered(filename, command, opts);
+    }
+    let by_extension;
+    let opts = match markup::Format::for_file(filename) {
+        Some(format) if opts.markup.is_none() => {
+            log::debug!("{}: counting the text of {:?}", filename, format);
+            by_extension = CountOptions {
+                markup: Some(format),
+                ..opts.clone()
+            };
+            &by_extension
+        }
+        _ => opts,
+    };
+    // Notebook prose is Markdown; code is only counted in lines
+    if let Some(notebook) = documents::notebook(filename)? {
+        log::info!("{}: read as a Jupyter notebook", filename);
+        let opts = CountOptions {
+            markup: Some(markup::Format::Markdown),
+            ..opts.clone()
+        };
+        let mut m = count_reader(&mut notebook.markdown.as_bytes(), filename, &opts)?;
+        m.cells = notebook.cells;
+        m.code_lines = notebook.code_lines;
+        return Ok(m);
+    }
+    if let Some(text) = documents::extract(filename)? {
+        log::info!("{}: extracted the text of the document", filename);
+        let mut m = count_reader(&mut text.as_bytes(), filename, opts)?;
+        if text.trim().is_empty() {
+            m.filename = i18n::message("no-text", &[("file", m.filename.as_str().into())]);
+        }
+        return Ok(m);
+    }
+    let mut f = File::open(filename)?;
+    log::info!("{}: opened", filename);
+    if opts.no_readahead {
+        priority::no_readahead(&f);
+    }
+    let seeked;
+    let opts = match opts.byte_range {
+        Some(range) if range.start.is_some() && f.metadata()?.is_file() => {
+            seeked = seek_to_range(&mut f, range, opts)?;
+            &seeked
+        }
+        _ => opts,
+    };
+    let from_tail;
+    let opts = match opts.tail {
+        // Which lines are the last ones is only known once the others are left out
+        Some(count)
+            if opts.match_lines.is_none()
+                && opts.ignore_lines.is_none()
+                && f.metadata()?.is_file() =>
+        {
+            let start = select::tail_start(&mut f, count)?;
+            log::debug!("{}: last {} lines start at byte {}", filename, count, start);
+            f.seek(io::SeekFrom::Start(start))?;
+            from_tail = CountOptions {
+                tail: None,
+                ..opts.clone()
+            };
+            &from_tail
+        }
+        _ => opts,
+    };
+    if let Some(percent) = opts.estimate {
+        if let Some(m) = estimate::count(&mut f, filename, percent, opts)? {
+            return Ok(m);
+        }
+        f.rewind()?;
+    }
+    if opts.detect_encoding {
+        let (encoding, confident, sample) = detect_encoding(&mut f)?;
+        log::debug!(
+            "{}: detected {} ({})",
+            filename,
+            encoding.name(),
+            if confident { "certain" } else { "uncertain" }
+        );
+        let opts = CountOptions {
+            encoding: Some(encoding),
+            ..opts.clone()
+        };
+        let mut m = count_reader(
+            &mut BufReader::with_capacity(1024, io::Cursor::new(sample).chain(f)),
+            filename,
+            &opts,
+        )?;
+        m.encoding = Some(encoding.name().to_owned());
+        m.encoding_confident = Some(confident);
+        Ok(m)
+    } else if let Some(raw) = opts.raw {
+        count_raw(f, filename, raw, opts)
+    } else {
+        count_reader(&mut BufReader::with_capacity(1024, f), filename, opts)
+    }
+}
+
+// Start reading a file at the start of the byte range, instead of skipping up to it.
+// Returns the options for counting from there.
+fn seek_to_range(f: &mut File, range: Range, opts: &CountOptions) -> Result<CountOptions, Error> {
+    let start = range.start.unwrap_or(0);
+    f.seek(io::SeekFrom::Start(start))?;
+    Ok(CountOptions {
+        byte_range: Some(Range {
+            start: None,
+            end: range.end.map(|end| end - start),
+        }),
+        ..opts.clone()
+    })
+}
+
+// Reads for counting bytes and lines only are this large, to make fewer system calls
+const RAW_BUFFER_SIZE: usize = 256 * 1024;
+
+//
+// Count only bytes, or bytes and lines, without decoding the text.
+// The bytes of a pipe are not even copied where the system allows.
+//
+fn count_raw(
+    mut f: File,
+    filename: &str,
+    raw: RawCounts,
+    opts: &CountOptions,
+) -> Result<Metrics, Error> {
+    let mut m = Metrics {
+        filename: filename.to_owned(),
+        ..Default::default()
+    };
+    if raw == RawCounts::Bytes {
+        if let Some(bytes) = pipe::discard(&f)? {
+            m.bytes = bytes as usize;
+            return Ok(m);
+        }
+    }
+    let mut buffer = vec![0; RAW_BUFFER_SIZE];
+    let mut first = true;
+    loop {
+        let n = match f.read(&mut buffer) {
+            Ok(n) => n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        // Line breaks in UTF-16 are more than a byte
+        if first && (buffer[..n].starts_with(b"\xff\xfe") || buffer[..n].starts_with(b"\xfe\xff")) {
+            let mut input = BufReader::new(io::Cursor::new(buffer[..n].to_vec()).chain(f));
+            return count_reader(&mut input, filename, opts);
+        }
+        first = false;
+        if n == 0 || signals::interrupted() {
+            return Ok(m);
+        }
+        m.bytes += n;
+        if raw == RawCounts::LinesAndBytes {
+            m.lines += buffer[..n].iter().filter(|&&byte| byte == b'\n').count();
+        }
+        signals::read(n);
+    }
+}
+
+//
+// Count what a shell command like `pandoc -t plain` makes of a file given as its standard
+// input, for formats wc cannot read itself. The counts are labeled with the file name.
+//
+fn count_filtered(filename: &str, command: &str, opts: &CountOptions) -> Result<Metrics, Error> {
+    let mut child = shell(command)
+        .stdin(File::open(filename)?)
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let output = child.stdout.take().expect("output of the command is piped");
+    let counted = count_reader(&mut BufReader::new(output), filename, opts);
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(Error::other(format!(
+            "'{}' failed with {}",
+            command, status
+        )));
+    }
+    counted
+}
+
+//
+// Count a file on a thread of its own if there is a timeout, so that a file that never
+// delivers, like a FIFO nobody writes to or a hanging network share, can be given up on.
+// The thread is left behind, still waiting for the file.
+//
+fn count_in_time(filename: &str, opts: &CountOptions) -> Result<Metrics, Error> {
+    let Some(timeout) = opts.timeout else {
+        return count(filename, opts);
+    };
+    let (tx, rx) = std::sync::mpsc::channel();
+    let (name, thread_opts) = (filename.to_owned(), opts.clone());
+    let abandoned = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let flag = std::sync::Arc::clone(&abandoned);
+    std::thread::spawn(move || {
+        signals::abandon_on(flag);
+        // Nobody is listening anymore if the file took too long
+        let _ = tx.send(count(&name, &thread_opts));
+    });
+    rx.recv_timeout(timeout).unwrap_or_else(|_| {
+        abandoned.store(true, std::sync::atomic::Ordering::Relaxed);
+        let seconds = format!("{}", timeout.as_secs_f64());
+        Err(Error::new(
+            ErrorKind::TimedOut,
+            i18n::message("timed-out", &[("seconds", seconds.into())]),
+        ))
+    })
+}
+
+//
+// Count the sections of a file, each starting at a line that matches the pattern.
+// Text before the first match is a section of its own, if there is any.
+//
+fn count_sections(
+    filename: &str,
+    pattern: &regex::Regex,
+    opts: &CountOptions,
+) -> Result<Vec<Metrics>, Error> {
+    let mut input = BufReader::new(File::open(filename)?);
+    let mut sections = vec![];
+    let mut counter = Counter::new(&format!("{}: (start)", filename), opts);
+    let mut line = vec![];
+    while input.read_until(b'\n', &mut line)? > 0 {
+        let text = String::from_utf8_lossy(&line);
+        if pattern.is_match(text.trim_end_matches(['\n', '\r'])) {
+            let heading = format!("{}: {}", filename, text.trim());
+            let section = std::mem::replace(&mut counter, Counter::new(&heading, opts)).finish()?;
+            if !sections.is_empty() || section.bytes > 0 {
+                sections.push(section);
+            }
+        }
+        counter.update(&line)?;
+        line.clear();
+    }
+    let section = counter.finish()?;
+    if !sections.is_empty() || section.bytes > 0 {
+        sections.push(section);
+    }
+    Ok(sections)
+}
+
+//
+// Print the counts of one file, or the total, in columns of the widths in mwpc.
+// The counts in the highlighted columns are printed in red.
+//
+fn print_metrics(
+    out: &mut dyn io::Write,
+    m: &Metrics,
+    opts: &ShowOptions,
+    mwpc: &Metrics,
+    highlighted: &[Column],
+) {
+    let cell = |column: Column, width: usize| {
+        // Counts are only aligned in columns if they come before the file name
+        let width = if opts.filename_first { 0 } else { width };
+        let count = format!("{:>width$}", opts.format(column.value(m)), width = width);
+        if highlighted.contains(&column) {
+            format!("\x1b[1;31m{}\x1b[0m", count)
+        } else {
+            count
+        }
+    };
+    let mut row = vec![];
+    let mut remove_column =
+    if opts.is_default() || opts.lines {
+        row.push(cell(Column::Lines, mwpc.lines));
+        1
+    } else {
+        0
+    };
+    if opts.is_default() || opts.words {
+        row.push(cell(Column::Words, mwpc.words-remove_column));
+        remove_column = 1;
+    }
+    if opts.chars {
+        row.push(cell(Column::Chars, mwpc.chars-remove_column));
+        remove_column = 1;
+    }
+    if opts.is_default() || opts.bytes {
+        row.push(cell(Column::Bytes, mwpc.bytes-remove_column));
+        remove_column = 1;
+    }
+    if opts.graphemes {
+        row.push(cell(Column::Graphemes, mwpc.graphemes - remove_column));
+        remove_column = 1;
+    }
+    if opts.max_line_length {
+        row.push(cell(
+            Column::MaxLineLength,
+            mwpc.max_line_length - remove_column,
+        ));
+        remove_column = 1;
+    }
+    if opts.records {
+        row.push(cell(Column::Records, mwpc.records - remove_column));
+        row.push(cell(Column::Fields, mwpc.fields - 1));
+        remove_column = 1;
+    }
+    if opts.field_counts {
+        row.push(cell(Column::MinFields, mwpc.min_fields - 1));
+        row.push(cell(Column::ModalFields, mwpc.modal_fields - 1));
+    }
+    if opts.documents {
+        row.push(cell(Column::Documents, mwpc.documents - remove_column));
+        row.push(cell(Column::InvalidDocuments, mwpc.invalid_documents - 1));
+        remove_column = 1;
+    }
+    if opts.cues {
+        row.push(cell(Column::Cues, mwpc.cues - remove_column));
+        remove_column = 1;
+    }
+    if opts.unique_words {
+        row.push(cell(Column::UniqueWords, mwpc.unique_words - remove_column));
+        remove_column = 1;
+    }
+    if opts.messages {
+        row.push(cell(Column::Messages, mwpc.messages - remove_column));
+        remove_column = 1;
+    }
+    if opts.cells {
+        row.push(cell(Column::Cells, mwpc.cells - remove_column));
+        row.push(cell(Column::CodeLines, mwpc.code_lines - 1));
+        remove_column = 1;
+    }
+    if opts.log_levels {
+        for (count, width) in m.log_levels.iter().zip(&mwpc.log_levels) {
+            row.push(format!(
+                "{:>width$}",
+                opts.format(*count),
+                width = width - remove_column
+            ));
+            remove_column = 1;
+        }
+    }
+    if opts.listed_words {
+        for (count, width) in m.listed_words.iter().zip(&mwpc.listed_words) {
+            row.push(format!(
+                "{:>width$}",
+                opts.format(*count),
+                width = width - remove_column
+            ));
+            remove_column = 1;
+        }
+    }
+    if let Some((column, goal)) = opts.goal {
+        let progress = 100.0 * column.value(m) as f64 / goal as f64;
+        row.push(format!("{:>5.1}%", progress));
+    }
+    if opts.compressibility {
+        row.push(compress::ratio(m.bytes, m.compressed));
+    }
+    if opts.encoding {
+        row.push(format!("{:<12}", m.encoding.as_deref().unwrap_or("-")));
+        let confidence = match m.encoding_confident {
+            Some(true) => i18n::text("encoding-certain"),
+            Some(false) => i18n::text("encoding-uncertain"),
+            None => "-".to_owned(),
+        };
+        row.push(format!("{:<9}", confidence));
+    }
+    let filename = match m.margins {
+        Some(margins) => i18n::message(
+            "estimated",
+            &[
+                ("label", m.filename.as_str().into()),
+                ("lines", margins.lines.into()),
+                ("words", margins.words.into()),
+                ("chars", margins.chars.into()),
+            ],
+        ),
+        None => m.filename.clone(),
+    };
+    let filename = match &m.unique {
+        Some(unique) if opts.unique_words => i18n::message(
+            "unique-margin",
+            &[
+                ("label", filename.as_str().into()),
+                ("margin", unique.margin().into()),
+            ],
+        ),
+        _ => filename,
+    };
+    if opts.filename_first {
+        let counts: Vec<&str> = row.iter().map(|count| count.trim()).collect();
+        writeln!(out, "{}\t{}", filename, counts.join("\t")).unwrap();
+    } else {
+        for count in &row {
+            write!(out, "{} ", count).unwrap();
+        }
+        writeln!(out, "{}", filename).unwrap();
+    }
+}
+
+fn calculate_total_and_max_width_per_column(ms: &[Metrics]) -> (Metrics, Metrics) {
+    let mut total = Metrics {
+        filename: i18n::text("total"),
+        ..Default::default()
+    };
+    let mut mwpc = Metrics::default(); // Width of filename is not important
+    for m_x in ms {
+        total.bytes += m_x.bytes;
+        total.chars += m_x.chars;
+        total.lines += m_x.lines;
+        total.words += m_x.words;
+        total.graphemes += m_x.graphemes;
+        total.records += m_x.records;
+        total.fields = std::cmp::max(total.fields, m_x.fields);
+        if m_x.records > 0 {
+            total.min_fields = if total.records == m_x.records {
+                m_x.min_fields
+            } else {
+                std::cmp::min(total.min_fields, m_x.min_fields)
+            };
+        }
+        total.modal_fields = std::cmp::max(total.modal_fields, m_x.modal_fields);
+        total.documents += m_x.documents;
+        total.invalid_documents += m_x.invalid_documents;
+        total.cues += m_x.cues;
+        total.messages += m_x.messages;
+        total.cells += m_x.cells;
+        total.code_lines += m_x.code_lines;
+        if total.log_levels.len() < m_x.log_levels.len() {
+            total.log_levels.resize(m_x.log_levels.len(), 0);
+        }
+        for (sum, count) in total.log_levels.iter_mut().zip(&m_x.log_levels) {
+            *sum += count;
+        }
+        if total.listed_words.len() < m_x.listed_words.len() {
+            total.listed_words.resize(m_x.listed_words.len(), 0);
+        }
+        for (sum, count) in total.listed_words.iter_mut().zip(&m_x.listed_words) {
+            *sum += count;
+        }
+        total.max_line_length = std::cmp::max(total.max_line_length, m_x.max_line_length);
+        if let Some(compressed) = m_x.compressed {
+            *total.compressed.get_or_insert(0) += compressed;
+        }
+        // Adding up the margins overstates them, as the errors partly cancel out
+        if let Some(margins) = m_x.margins {
+            let sum = total.margins.get_or_insert_with(Margins::default);
+            sum.lines += margins.lines;
+            sum.words += margins.words;
+            sum.chars += margins.chars;
+        }
+        mwpc.bytes = std::cmp::max(mwpc.bytes, m_x.bytes);
+        mwpc.chars = std::cmp::max(mwpc.chars, m_x.chars);
+        mwpc.lines = std::cmp::max(mwpc.lines, m_x.lines);
+        mwpc.words = std::cmp::max(mwpc.words, m_x.words);
+        mwpc.graphemes = std::cmp::max(mwpc.graphemes, m_x.graphemes);
+        mwpc.records = std::cmp::max(mwpc.records, m_x.records);
+        mwpc.documents = std::cmp::max(mwpc.documents, m_x.documents);
+        mwpc.invalid_documents = std::cmp::max(mwpc.invalid_documents, m_x.invalid_documents);
+        mwpc.cues = std::cmp::max(mwpc.cues, m_x.cues);
+        if let Some(unique) = &m_x.unique {
+            total
+                .unique
+                .get_or_insert_with(hyperloglog::Sketch::default)
+                .merge(unique);
+        }
+        mwpc.unique_words = std::cmp::max(mwpc.unique_words, m_x.unique_words);
+        mwpc.messages = std::cmp::max(mwpc.messages, m_x.messages);
+        mwpc.cells = std::cmp::max(mwpc.cells, m_x.cells);
+        mwpc.code_lines = std::cmp::max(mwpc.code_lines, m_x.code_lines);
+        // mwpc.max_line_length not needed again
+    }
+    mwpc.bytes = std::cmp::max(mwpc.bytes.to_string().len(), 8);
+    mwpc.chars = std::cmp::max(mwpc.chars.to_string().len(), 8);
+    mwpc.lines = std::cmp::max(mwpc.lines.to_string().len(), 8);
+    mwpc.words = std::cmp::max(mwpc.words.to_string().len(), 8);
+    mwpc.graphemes = std::cmp::max(mwpc.graphemes.to_string().len(), 8);
+    mwpc.records = std::cmp::max(mwpc.records.to_string().len(), 8);
+    mwpc.fields = std::cmp::max(total.fields.to_string().len(), 8);
+    mwpc.min_fields = std::cmp::max(total.min_fields.to_string().len(), 8);
+    mwpc.modal_fields = std::cmp::max(total.modal_fields.to_string().len(), 8);
+    mwpc.documents = std::cmp::max(mwpc.documents.to_string().len(), 8);
+    mwpc.invalid_documents = std::cmp::max(mwpc.invalid_documents.to_string().len(), 8);
+    mwpc.cues = std::cmp::max(mwpc.cues.to_string().len(), 8);
+    total.unique_words = total
+        .unique
+        .as_ref()
+        .map_or(0, hyperloglog::Sketch::estimate);
+    mwpc.unique_words = std::cmp::max(total.unique_words.to_string().len(), 8);
+    mwpc.messages = std::cmp::max(mwpc.messages.to_string().len(), 8);
+    mwpc.cells = std::cmp::max(mwpc.cells.to_string().len(), 8);
+    mwpc.code_lines = std::cmp::max(mwpc.code_lines.to_string().len(), 8);
+    mwpc.log_levels = total
+        .log_levels
+        .iter()
+        .map(|count| std::cmp::max(count.to_string().len(), 8))
+        .collect();
+    mwpc.listed_words = total
+        .listed_words
+        .iter()
+        .map(|count| std::cmp::max(count.to_string().len(), 8))
+        .collect();
+    mwpc.max_line_length = std::cmp::max(total.max_line_length.to_string().len(), 8);
+    (total, mwpc)
+}
+
+fn print_table(out: &mut dyn io::Write, all_metrics: &[Metrics], opts: &ShowOptions) {
+    let (mut total, mut mwpc) = calculate_total_and_max_width_per_column(all_metrics);
+    if let Some(grouping) = &opts.grouping {
+        for width in &mut [
+            &mut mwpc.lines,
+            &mut mwpc.words,
+            &mut mwpc.chars,
+            &mut mwpc.bytes,
+            &mut mwpc.graphemes,
+            &mut mwpc.max_line_length,
+            &mut mwpc.records,
+            &mut mwpc.fields,
+            &mut mwpc.min_fields,
+            &mut mwpc.modal_fields,
+            &mut mwpc.documents,
+            &mut mwpc.invalid_documents,
+            &mut mwpc.cues,
+            &mut mwpc.unique_words,
+            &mut mwpc.messages,
+            &mut mwpc.cells,
+            &mut mwpc.code_lines,
+        ] {
+            **width = grouping.width(**width);
+        }
+        for width in mwpc.log_levels.iter_mut().chain(&mut mwpc.listed_words) {
+            *width = grouping.width(*width);
+        }
+    }
+    // Filters, sorting and --top only affect the rows, the total still covers
+    // every file unless --filter-total is given
+    let mut rows: Vec<&Metrics> = all_metrics
+        .iter()
+        .filter(|m| {
+            let listed = opts.is_listed(m);
+            if !listed {
+                log::debug!("{}: not listed, filtered out", m.filename);
+            }
+            listed
+        })
+        .collect();
+    if opts.filter_total {
+        let listed: Vec<Metrics> = rows.iter().map(|m| (*m).clone()).collect();
+        total = calculate_total_and_max_width_per_column(&listed).0;
+    }
+    total.filename = opts.total_label();
+    if opts.partial {
+        total.filename = i18n::message("partial", &[("label", total.filename.as_str().into())]);
+    }
+    // Files that are equal in all keys stay in the order they were counted in
+    rows.sort_by(|a, b| {
+        opts.sort
+            .iter()
+            .fold(std::cmp::Ordering::Equal, |ordering, key| {
+                ordering.then_with(|| key.compare(a, b))
+            })
+    });
+    if let Some(top) = opts.top {
+        rows.truncate(top);
+    }
+    let print_total = all_metrics.len() > 1;
+    if print_total && opts.total_first {
+        print_metrics(out, &total, opts, &mwpc, &opts.highlighted(&total, true));
+    }
+    for (i, m) in rows.iter().enumerate() {
+        for section in &m.sections {
+            print_metrics(out, section, opts, &mwpc, &[]);
+        }
+        print_metrics(out, m, opts, &mwpc, &opts.highlighted(m, false));
+        // A running total of the files so far, unless the total follows anyway
+        if let Some(every) = opts.subtotal_every {
+            if (i + 1) % every == 0 && (i + 1 < rows.len() || opts.total_first) {
+                let listed: Vec<Metrics> = rows[..=i].iter().map(|m| (*m).clone()).collect();
+                let (mut subtotal, _) = calculate_total_and_max_width_per_column(&listed);
+                subtotal.filename = i18n::text("subtotal");
+                print_metrics(out, &subtotal, opts, &mwpc, &[]);
+            }
+        }
+    }
+    if print_total && !opts.total_first {
+        print_metrics(out, &total, opts, &mwpc, &opts.highlighted(&total, true));
+    }
+}
+
+// Parse a goal like words=50000 into the column and its target
+fn parse_goal(goal: &str) -> Result<(Column, usize), Error> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid --goal '{}'", goal),
+        )
+    };
+    let (name, target) = goal.split_once('=').ok_or_else(invalid)?;
+    let column = Column::parse(name)?;
+    let target = if column == Column::Bytes {
+        parse_size(target)? as usize
+    } else {
+        target.parse().map_err(|_| invalid())?
+    };
+    if target == 0 {
+        return Err(invalid());
+    }
+    Ok((column, target))
+}
+
+//
+// Sum up the counts of all files below the same leading directories, like `du -d`.
+// Files with fewer directories than depth are grouped under their own directory.
+// A root, drive or UNC share is kept with the first directory below it.
+//
+fn group_by_dir(all_metrics: &[Metrics], depth: usize) -> Vec<Metrics> {
+    use std::path::{Component, Path, PathBuf};
+
+    let mut groups: Vec<(String, Vec<Metrics>)> = vec![];
+    for m in all_metrics {
+        let dir: PathBuf = Path::new(&m.filename)
+            .parent()
+            .map(|parent| {
+                parent
+                    .components()
+                    .filter(|c| *c != Component::CurDir)
+                    .scan(0, |dirs, c| {
+                        if !matches!(c, Component::Prefix(_) | Component::RootDir) {
+                            *dirs += 1;
+                        }
+                        (*dirs <= depth).then_some(c)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let dir = match dir.to_string_lossy() {
+            dir if dir.is_empty() => ".".to_owned(),
+            dir => dir.into_owned(),
+        };
+        match groups.iter_mut().find(|(name, _)| *name == dir) {
+            Some((_, members)) => members.push(m.clone()),
+            None => groups.push((dir, vec![m.clone()])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(dir, members)| {
+            let (mut total, _) = calculate_total_and_max_width_per_column(&members);
+            total.filename = dir;
+            total
+        })
+        .collect()
+}
+
+//
+// cmd and PowerShell leave wildcards to the program, so expand them like a Unix shell would.
+// Patterns without matches are kept as they are, to be reported as not found.
+// Verbatim paths (\\?\C:\...) are never patterns.
+//
+#[cfg(windows)]
+fn expand_wildcards(files: &[String]) -> Vec<String> {
+    let mut expanded = vec![];
+    for file in files {
+        let is_pattern = !file.starts_with(r"\\?\") && file.contains(['*', '?', '[']);
+        let matches: Vec<String> = match glob::glob(file) {
+            Ok(paths) if is_pattern => paths
+                .filter_map(Result::ok)
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect(),
+            _ => vec![],
+        };
+        if matches.is_empty() {
+            expanded.push(file.clone());
+        } else {
+            expanded.extend(matches);
+        }
+    }
+    expanded
+}
+
+#[cfg(not(windows))]
+fn expand_wildcards(files: &[String]) -> Vec<String> {
+    files.to_vec()
+}
+
+// Dotfiles and files in dot-directories, e.g. .gitignore or .github/workflows/ci.yml
+fn is_hidden(file: &str) -> bool {
+    std::path::Path::new(file).components().any(|c| match c {
+        std::path::Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+        _ => false,
+    })
+}
+
+// Character and block devices, FIFOs and sockets
+#[cfg(unix)]
+fn is_special_file(file_type: &std::fs::FileType) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    file_type.is_char_device()
+        || file_type.is_block_device()
+        || file_type.is_fifo()
+        || file_type.is_socket()
+}
+
+#[cfg(not(unix))]
+fn is_special_file(_file_type: &std::fs::FileType) -> bool {
+    false
+}
+
+// A command line run by the shell of the system
+fn shell(command: &str) -> Command {
+    let mut shell;
+    if cfg!(windows) {
+        shell = Command::new("cmd");
+        shell.args(["/C", command]);
+    } else {
+        shell = Command::new("sh");
+        shell.args(["-c", command]);
+    }
+    shell
+}
+
+// Run a shell command and read its output as a list of file names,
+// separated by NUL characters if there are any, otherwise by newlines.
+fn files_from_command(command: &str) -> Result<Vec<String>, Error> {
+    let output = shell(command).output()?;
+    if !output.status.success() {
+        return Err(Error::other(format!(
+            "'{}' failed with {}",
+            command, output.status
+        )));
+    }
+    let stdout =
+        String::from_utf8(output.stdout).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+    let separator = if stdout.contains('\0') { '\0' } else { '\n' };
+    Ok(stdout
+        .split(separator)
+        .map(|name| name.strip_suffix('\r').unwrap_or(name))
+        .filter(|name| !name.is_empty())
+        .map(str::to_owned)
+        .collect())
+}
+
+// Names of the files to count, or None if standard input is to be counted.
+// Files named on the command line are always counted,
+// files discovered by wc itself are subject to the usual filters.
+fn input_files(cli: &Cli) -> Result<Option<Vec<String>>, Error> {
+    let (mut files, discovered): (Vec<String>, bool) = if cli.git || cli.staged {
+        (git::files(cli.staged)?, true)
+    } else if let Some(command) = &cli.files_from_cmd {
+        (files_from_command(command)?, false)
+    } else if !cli.files.is_empty() {
+        (expand_wildcards(&cli.files), false)
+    } else {
+        return Ok(None);
+    };
+    if discovered && !cli.hidden {
+        files.retain(|file| {
+            let hidden = is_hidden(file);
+            if hidden {
+                log::info!("{}: skipped, hidden", file);
+                stats::skipped();
+            }
+            !hidden
+        });
+    }
+    let skip_devices = match cli.devices {
+        Some(devices) => devices == Devices::Skip,
+        None => discovered,
+    };
+    if skip_devices {
+        files.retain(|file| match std::fs::metadata(file) {
+            Ok(meta) if is_special_file(&meta.file_type()) => {
+                stats::skipped();
+                if !cli.quiet {
+                    errors::report(file, "skipped", &i18n::text("skipped-special"));
+                }
+                false
+            }
+            _ => true,
+        });
+    }
+    if let Some(max) = cli.max_filesize {
+        files.retain(|file| match std::fs::metadata(file) {
+            Ok(meta) if meta.is_file() && meta.len() > max => {
+                stats::skipped();
+                if !cli.quiet {
+                    let message = i18n::message("skipped-larger", &[("max", max.into())]);
+                    errors::report(file, "skipped", &message);
+                }
+                false
+            }
+            _ => true,
+        });
+    }
+    Ok(Some(files))
+}
+
+fn print_count(out: &mut dyn io::Write, cli: &Cli) -> Result<(), Error> {
+    print_count_since(out, cli, &mut vec![])
+}
+
+// The checks of --fail-if, --fail-on-empty and --fields, whatever the counts were read from
+fn check_counts(
+    cli: &Cli,
+    conditions: &[threshold::Condition],
+    all_metrics: &[Metrics],
+) -> Result<(), Error> {
+    threshold::check(conditions, all_metrics)
+        .and(if cli.fail_on_empty {
+            threshold::check_empty(all_metrics)
+        } else {
+            Ok(())
+        })
+        .and(if cli.fields.is_some() {
+            threshold::check_fields(all_metrics)
+        } else {
+            Ok(())
+        })
+}
+
+//
+// Like print_count, but with --live, the counts that changed since those printed before
+// are highlighted. These are then replaced by the counts printed now.
+//
+fn print_count_since(
+    out: &mut dyn io::Write,
+    cli: &Cli,
+    printed: &mut Vec<Metrics>,
+) -> Result<(), Error> {
+    let mut opts = ShowOptions::from_cli(cli)?;
+    if cli.live {
+        opts.changed_since = Some(std::mem::take(printed));
+    }
+    let mut count_opts = CountOptions::from_cli(cli)?;
+    let conditions = cli
+        .fail_if
+        .iter()
+        .map(|condition| threshold::Condition::parse(condition))
+        .collect::<Result<Vec<_>, _>>()?;
+    // Counts saved for later runs must be complete
+    if !count_opts.needs_text() && cli.save_baseline.is_none() && cli.snapshot.is_none() {
+        let uses_only = |columns: &[Column]| {
+            opts.uses_only(columns)
+                && conditions
+                    .iter()
+                    .all(|condition| columns.contains(&condition.column()))
+        };
+        if uses_only(&[Column::Bytes]) {
+            count_opts.raw = Some(RawCounts::Bytes);
+        } else if uses_only(&[Column::Lines, Column::Bytes]) {
+            count_opts.raw = Some(RawCounts::LinesAndBytes);
+        }
+    }
+    let mut stats = stats::Stats::start();
+    let previous = match &cli.incremental {
+        Some(state) => incremental::load(state)?,
+        None => vec![],
+    };
+    if let Some(files) = input_files(cli)? {
+        let mut all_metrics = vec![];
+        let mut unreadable = Ok(());
+        let mut resumes = vec![];
+        if cli.combine {
+            // One stream, like `cat files | wc`, but naming the files that cannot be read
+            let mut counter = Counter::new(&opts.total_label(), &count_opts);
+            for file in &files {
+                signals::start_file(file);
+                if let Err(err) =
+                    File::open(file).and_then(|f| feed(&mut counter, &mut BufReader::new(f)))
+                {
+                    if !opts.quiet {
+                        errors::report_error(file, &err);
+                    }
+                    stats.errors += 1;
+                    if count_opts.on_error == ErrorPolicy::Strict {
+                        return Err(errors::reported(err));
+                    }
+                    unreadable = Err(errors::reported(err));
+                } else {
+                    stats.files += 1;
+                }
+                signals::file_done();
+                if signals::interrupted() {
+                    break;
+                }
+            }
+            all_metrics.push(counter.finish()?);
+        } else {
+            // One file after the other, so the rows keep the order the files were given or found
+            for file in files {
+                signals::start_file(&file);
+                let counted = match cli.incremental {
+                    Some(_) => {
+                        incremental::count(&file, &previous, &count_opts).map(|(m, resume)| {
+                            resumes.push(resume);
+                            m
+                        })
+                    }
+                    None => count_in_time(&file, &count_opts),
+                };
+                let counted = counted.and_then(|mut m| {
+                    if let Some(pattern) = &cli.split_by {
+                        m.sections = count_sections(&file, pattern, &count_opts)?;
+                    }
+                    Ok(m)
+                });
+                match counted {
+                    Ok(m) => {
+                        all_metrics.push(m);
+                        stats.files += 1;
+                    }
+                    Err(err) => {
+                        if !opts.quiet {
+                            errors::report_error(&file, &err);
+                        }
+                        stats.errors += 1;
+                        if count_opts.on_error == ErrorPolicy::Strict {
+                            return Err(errors::reported(err));
+                        }
+                        unreadable = Err(errors::reported(err));
+                    }
+                }
+                signals::file_done();
+                if signals::interrupted() {
+                    break;
+                }
+            }
+        }
+        let bytes = all_metrics.iter().map(|m| m.bytes).sum();
+        let print_stats = || {
+            if cli.stats {
+                eprintln!("wc: {}", stats.report(bytes));
+            }
+        };
+        if signals::interrupted() {
+            // Show what was counted, but do not check or save incomplete counts
+            if let Some(last) = all_metrics.last_mut() {
+                last.filename =
+                    i18n::message("partial", &[("label", last.filename.as_str().into())]);
+            }
+            opts.partial = true;
+            print_table(out, &all_metrics, &opts);
+            print_stats();
+            eprintln!("wc: {}", i18n::text("interrupted"));
+            return Err(errors::reported(Error::from(ErrorKind::Interrupted)));
+        }
+        if let Some(path) = &cli.export_vocab {
+            vocabulary::export(path, &all_metrics)?;
+        }
+        if let Some(path) = &cli.byte_histogram {
+            histogram::export(path, &all_metrics)?;
+        }
+        if let Some(path) = &cli.export_line_lengths {
+            histogram::export_line_lengths(path, &all_metrics)?;
+        }
+        // Budgets apply to the files, even if they are printed grouped
+        let within_budget = check_counts(cli, &conditions, &all_metrics);
+        if let Some(depth) = opts.group_by {
+            all_metrics = group_by_dir(&all_metrics, depth);
+        }
+        if let Some(baseline) = &cli.diff {
+            baseline::diff(out, baseline, &all_metrics, &opts, cli.changed_only)?;
+        } else if let Some(snapshot) = &cli.since {
+            baseline::since(out, snapshot, &all_metrics, &opts)?;
+        } else {
+            print_table(out, &all_metrics, &opts);
+        }
+        if cli.live {
+            printed.clone_from(&all_metrics);
+        }
+        if let Some(state) = &cli.incremental {
+            incremental::save(state, &resumes)?;
+        }
+        if let Some(baseline) = &cli.save_baseline {
+            baseline::save(baseline, &all_metrics)?;
+        }
+        if let Some(snapshot) = &cli.snapshot {
+            baseline::save(snapshot, &all_metrics)?;
+        }
+        print_stats();
+        within_budget?;
+        unreadable?;
+    } else if let Some(fd) = cli.fd {
+        let f = console::inherited(fd)?;
+        let m = match count_opts.raw {
+            Some(raw) => count_raw(f, "", raw, &count_opts)?,
+            None => count_reader(&mut BufReader::new(f), "", &count_opts)?,
+        };
+        let all_metrics = [m];
+        print_table(out, &all_metrics, &opts);
+        check_counts(cli, &conditions, &all_metrics)?;
+    } else {
+        // Stdin
+    }
+
+    Ok(())
+}
+
+//
+// Print the line number, words, characters and display width of every line,
+// each line counted with the same rules as whole files. The whole input is decoded
+// as one, so that e.g. the byte order mark of UTF-16 applies to all its lines.
+//
+fn print_lines(
+    out: &mut dyn io::Write,
+    input: &mut dyn BufRead,
+    filename: &str,
+    opts: &ShowOptions,
+    count_opts: &CountOptions,
+) -> Result<(), Error> {
+    let mut counter = Counter::new(filename, count_opts);
+    let mut number = 0;
+    let mut print = |lines: Vec<LineCounts>| -> Result<(), Error> {
+        for line in lines {
+            number += 1;
+            writeln!(
+                out,
+                "{:>7} {:>7} {:>7} {:>7} {}",
+                opts.format(number),
+                opts.format(line.words),
+                opts.format(line.chars),
+                opts.format(line.width),
+                filename
+            )?;
+        }
+        Ok(())
+    };
+    loop {
+        let buffer = input.fill_buf()?;
+        if buffer.is_empty() || signals::interrupted() || counter.is_done() {
+            break;
+        }
+        let len = buffer.len();
+        counter.update(buffer)?;
+        input.consume(len);
+        print(counter.take_line_counts())?;
+    }
+    counter.end_input()?;
+    print(counter.take_line_counts())
+}
+
+fn per_line(out: &mut dyn io::Write, cli: &Cli) -> Result<(), Error> {
+    let opts = ShowOptions::from_cli(cli)?;
+    let count_opts = CountOptions::from_cli(cli)?;
+    let mut unreadable = Ok(());
+    for file in input_files(cli)?.unwrap_or_default() {
+        let printed = File::open(&file)
+            .and_then(|f| print_lines(out, &mut BufReader::new(f), &file, &opts, &count_opts));
+        if let Err(err) = printed {
+            if !opts.quiet {
+                errors::report_error(&file, &err);
+            }
+            if count_opts.on_error == ErrorPolicy::Strict {
+                return Err(errors::reported(err));
+            }
+            unreadable = Err(errors::reported(err));
+        }
+    }
+    unreadable
+}
+
+// Print the files that would be counted, one per line, to check the options that select them
+fn list_files(out: &mut dyn io::Write, cli: &Cli) -> Result<(), Error> {
+    for file in input_files(cli)?.unwrap_or_default() {
+        writeln!(out, "{}", file)?;
+    }
+    Ok(())
+}
+
+// A line consisting of just this ends the text in interactive mode
+const INTERACTIVE_SENTINEL: &[u8] = b".";
+
+//
+// Read text typed or pasted into the terminal until EOF or the sentinel line,
+// print its counts and start over. Stops at an EOF that comes without any text.
+//
+fn interactive(out: &mut dyn io::Write, cli: &Cli) -> Result<(), Error> {
+    let opts = ShowOptions::from_cli(cli)?;
+    let count_opts = CountOptions::from_cli(cli)?;
+    let prompt = io::stdin().is_terminal();
+    let mut input = console::stdin();
+    loop {
+        if prompt {
+            eprintln!("{}", i18n::text("interactive-prompt"));
+        }
+        let mut counter = Counter::new("", &count_opts);
+        let mut line = vec![];
+        let mut read_any = false;
+        loop {
+            line.clear();
+            if input.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            read_any = true;
+            let content = line.strip_suffix(b"\n").unwrap_or(&line);
+            if content.strip_suffix(b"\r").unwrap_or(content) == INTERACTIVE_SENTINEL {
+                break;
+            }
+            counter.update(&line)?;
+        }
+        if !read_any {
+            return Ok(());
+        }
+        print_table(out, &[counter.finish()?], &opts);
+        out.flush()?;
+    }
+}
+
+#[cfg(unix)]
+fn daemon(socket: &str, opts: &CountOptions) -> Result<(), Error> {
+    daemon::serve(socket, opts)
+}
+
+#[cfg(not(unix))]
+fn daemon(_socket: &str, _opts: &CountOptions) -> Result<(), Error> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        "--daemon is only supported on Unix",
+    ))
+}
+
+// Print a completion script for the shell to stdout
+fn completions(shell: clap_complete::Shell) {
+    clap_complete::generate(shell, &mut Cli::command(), "wc", &mut io::stdout());
+}
+
+//
+// Parse the command line with the defaults of the config file and the environment
+// inserted before it. Options from the environment take precedence over the config file,
+// and those on the command line over both.
+//
+fn parse_args() -> Cli {
+    let args: Vec<String> = std::env::args().collect();
+    let matches = Cli::command().get_matches_from(&args);
+    let config = matches.get_one::<String>("config").map(String::as_str);
+    let mut defaults = match config::default_args(config) {
+        Ok(defaults) => defaults,
+        Err(err) => {
+            eprintln!("wc: config: {}", err);
+            std::process::exit(1);
+        }
+    };
+    let parser = Cli::command();
+    let long_names: Vec<&str> = parser
+        .get_arguments()
+        .filter_map(|arg| arg.get_long())
+        .collect();
+    let env = config::env_args(std::env::vars(), &long_names);
+    defaults.retain(|(key, _)| !env.iter().any(|(env_key, _)| env_key == key));
+    defaults.extend(env);
+    // Unknown keys are kept, for clap to report them
+    let on_command_line = |key: &str| {
+        parser
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(key))
+            .is_some_and(|arg| {
+                matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+            })
+    };
+    let defaults: Vec<String> = defaults
+        .into_iter()
+        .filter(|(key, _)| !on_command_line(key))
+        .map(|(_, arg)| arg)
+        .collect();
+    let matches = if defaults.is_empty() {
+        matches
+    } else {
+        Cli::command().get_matches_from(
+            args.iter()
+                .take(1)
+                .chain(&defaults)
+                .chain(args.iter().skip(1)),
+        )
+    };
+    Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit())
+}
+
+// -v reports what happens to each file, -vv also how it is counted
+fn init_logging(verbosity: u8) {
+    let level = match verbosity {
+        0 => log::LevelFilter::Warn,
+        1 => log::LevelFilter::Info,
+        _ => log::LevelFilter::Debug,
+    };
+    env_logger::Builder::new()
+        .filter_level(level)
+        .parse_default_env()
+        .format_timestamp(None)
+        .format_target(false)
+        .init();
+}
+
+// TODO: read from stdin if no files are given
+// TODO: files0_from
+fn main() {
+    let cli = parse_args();
+    init_logging(cli.verbose);
+    i18n::init(cli.lang.as_deref());
+    errors::init(cli.errors);
+    if cli.nice {
+        priority::lower();
+    }
+
+    let result = if let Some(cli::Command::Completions { shell }) = cli.command {
+        completions(shell);
+        Ok(())
+    } else if cli.generate_man {
+        clap_mangen::Man::new(Cli::command()).render(&mut io::stdout().lock())
+    } else if let Some(dir) = &cli.self_test {
+        selftest::self_test(&mut io::stdout().lock(), dir.as_deref())
+    } else if cli.cross_dupes {
+        ShowOptions::from_cli(&cli).and_then(|opts| {
+            let files = input_files(&cli)?.unwrap_or_default();
+            let max_memory = cli.max_memory.map(|size| size as usize);
+            dupes::print_cross_dupes(&mut io::stdout().lock(), &files, max_memory, &opts)
+        })
+    } else if cli.list_files {
+        list_files(&mut io::stdout().lock(), &cli)
+    } else if cli.watch {
+        watch::watch(&cli)
+    } else if cli.follow {
+        watch::follow(&cli)
+    } else if let Some(socket) = &cli.daemon {
+        CountOptions::from_cli(&cli).and_then(|count_opts| daemon(socket, &count_opts))
+    } else if let Some(manifest) = cli.check.as_ref().or(cli.verify.as_ref()) {
+        ShowOptions::from_cli(&cli).and_then(|opts| {
+            check::check(
+                manifest,
+                &opts,
+                &CountOptions::from_cli(&cli)?,
+                cli.verify.is_some(),
+            )
+        })
+    } else if let Some(paths) = &cli.compare {
+        ShowOptions::from_cli(&cli).and_then(|opts| {
+            let count_opts = CountOptions::from_cli(&cli)?;
+            compare::compare(
+                &mut io::stdout().lock(),
+                &paths[0],
+                &paths[1],
+                &opts,
+                &count_opts,
+            )
+        })
+    } else if cli.interactive {
+        interactive(&mut io::stdout().lock(), &cli)
+    } else if cli.per_line {
+        per_line(&mut io::stdout().lock(), &cli)
+    } else {
+        signals::catch_interrupt()
+            .and_then(|_| signals::catch_progress_request())
+            .and_then(|_| print_count(&mut io::stdout().lock(), &cli))
+    };
+    std::process::exit(match result {
+        // Each violation was reported as it was found
+        Err(err)
+            if err
+                .get_ref()
+                .is_some_and(|err| err.is::<threshold::Violated>()) =>
+        {
+            2
+        }
+        Err(err) if err.kind() == ErrorKind::Interrupted => {
+            if !errors::is_reported(&err) {
+                eprintln!("wc: {}", err);
+            }
+            130
+        }
+        Err(err) => {
+            if !errors::is_reported(&err) {
+                eprintln!("wc: {}", err);
+            }
+            1
+        }
+        Ok(_) => 0,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testfile::TempPath;
+    use crate::*;
+
+    fn sample_metrics() -> Vec<Metrics> {
+        let m0 = Metrics {
+            bytes: 123,
+            chars: 9_876_543_210,
+            lines: 789,
+            words: 1_239_875_670,
+            max_line_length: 456,
+            filename: "m0".to_owned(),
+            ..Default::default()
+        };
+        let m1 = Metrics {
+            bytes: 1_234_567_890,
+            chars: 1_234_567,
+            lines: 12_345_678_901,
+            words: 4_567_890,
+            max_line_length: 4_567_890_123,
+            filename: "m1".to_owned(),
+            ..Default::default()
+        };
+        vec![m0, m1]
+    }
+
+    #[test]
+    fn wider_columns() {
+        let metrics = sample_metrics();
+        let m0 = metrics[0].clone();
+        let m1 = metrics[1].clone();
+        let (total, mwpc) = calculate_total_and_max_width_per_column(&[m0.clone(), m1.clone()]);
+        assert_eq!(total.bytes, m0.bytes + m1.bytes);
+        assert_eq!(total.chars, m0.chars + m1.chars);
+        assert_eq!(total.lines, m0.lines + m1.lines);
+        assert_eq!(total.words, m0.words + m1.words);
+        assert_eq!(
+            total.max_line_length,
+            std::cmp::max(m0.max_line_length, m1.max_line_length)
+        );
+        assert_eq!(mwpc.bytes, 10);
+        assert_eq!(mwpc.chars, 10);
+        assert_eq!(mwpc.lines, 11);
+        assert_eq!(mwpc.words, 10);
+        //assert_eq!(mwpc.max_line_length, 10);
+    }
+
+    #[test]
+    fn print_wide_columns() {
+        let metrics = sample_metrics();
+        let m0 = metrics[0].clone();
+        let m1 = metrics[1].clone();
+        let mwpc = Metrics {
+            bytes: 10,
+            chars: 10,
+            lines: 11,
+            words: 10,
+            max_line_length: 10,
+            filename: "m1".to_owned(),
+            ..Default::default()
+        };
+        let opts = ShowOptions {
+            lines: true,
+            chars: true,
+            words: true,
+            max_line_length: true,
+            ..Default::default()
+        };
+        let mut writer = vec![];
+        print_metrics(&mut writer, &m0, &opts, &mwpc, &[]);
+        print_metrics(&mut writer, &m1, &opts, &mwpc, &[]);
+        let output = std::str::from_utf8(writer.as_ref()).unwrap();
+        assert_eq!(dbg!(output), "        789 1239875670 9876543210       456 m0\n12345678901   4567890   1234567 4567890123 m1\n")
+    }
+
+    #[test]
+    fn top_rows() {
+        let opts = ShowOptions {
+            lines: true,
+            sort: vec![SortKey {
+                column: Some(Column::Bytes),
+                descending: true,
+            }],
+            top: Some(1),
+            ..Default::default()
+        };
+        let mut writer = vec![];
+        print_table(&mut writer, &sample_metrics(), &opts);
+        let output = std::str::from_utf8(writer.as_ref()).unwrap();
+        assert_eq!(output, "12345678901 m1\n12345679690 total\n")
+    }
+
+    #[test]
+    fn sort_keys() {
+        let file = |filename: &str, lines: usize| Metrics {
+            lines,
+            filename: filename.to_owned(),
+            ..Default::default()
+        };
+        let all = [file("b", 2), file("c", 1), file("a", 2), file("d", 3)];
+        let sorted = |keys: &str| {
+            let opts = ShowOptions {
+                lines: true,
+                sort: SortKey::parse_list(keys).unwrap(),
+                ..Default::default()
+            };
+            let mut writer = vec![];
+            print_table(&mut writer, &all, &opts);
+            let output = String::from_utf8(writer).unwrap();
+            output
+                .lines()
+                .map(|line| line.split_whitespace().nth(1).unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sorted("lines"), ["d", "b", "a", "c", "total"]);
+        assert_eq!(sorted("lines:desc,name:asc"), ["d", "a", "b", "c", "total"]);
+        assert_eq!(sorted("lines:asc,name:desc"), ["c", "b", "a", "d", "total"]);
+        assert!(SortKey::parse_list("lines:up").is_err());
+        assert!(SortKey::parse_list("size").is_err());
+    }
+
+    #[test]
+    fn filter_rows() {
+        let mut opts = ShowOptions {
+            lines: true,
+            ranges: vec![(Column::Lines, 1000..=usize::MAX)],
+            ..Default::default()
+        };
+        let mut writer = vec![];
+        print_table(&mut writer, &sample_metrics(), &opts);
+        let output = std::str::from_utf8(writer.as_ref()).unwrap();
+        assert_eq!(output, "12345678901 m1\n12345679690 total\n");
+        opts.filter_total = true;
+        let mut writer = vec![];
+        print_table(&mut writer, &sample_metrics(), &opts);
+        let output = std::str::from_utf8(writer.as_ref()).unwrap();
+        assert_eq!(output, "12345678901 m1\n12345678901 total\n");
+        opts.ranges.clear();
+        opts.filter = Some(regex::Regex::new("m0").unwrap());
+        let mut writer = vec![];
+        print_table(&mut writer, &sample_metrics(), &opts);
+        let output = std::str::from_utf8(writer.as_ref()).unwrap();
+        assert_eq!(output, "        789 m0\n        789 total\n")
+    }
+
+    #[test]
+    fn highlight_cells() {
+        let mut opts = ShowOptions {
+            lines: true,
+            color: true,
+            highlight: vec![threshold::Condition::parse("lines>1000").unwrap()],
+            ..Default::default()
+        };
+        let mut writer = vec![];
+        print_table(&mut writer, &sample_metrics(), &opts);
+        let output = std::str::from_utf8(writer.as_ref()).unwrap();
+        assert_eq!(
+            output,
+            "        789 m0\n\x1b[1;31m12345678901\x1b[0m m1\n12345679690 total\n"
+        );
+        opts.color = false;
+        let mut writer = vec![];
+        print_table(&mut writer, &sample_metrics(), &opts);
+        let output = std::str::from_utf8(writer.as_ref()).unwrap();
+        assert_eq!(
+            output,
+            "        789 m0\n12345678901 m1\n12345679690 total\n"
+        )
+    }
+
+    #[test]
+    fn highlight_changes() {
+        let mut before = sample_metrics();
+        before[0].lines = 788;
+        let opts = ShowOptions {
+            lines: true,
+            color: true,
+            changed_since: Some(before),
+            ..Default::default()
+        };
+        let mut writer = vec![];
+        print_table(&mut writer, &sample_metrics(), &opts);
+        let output = std::str::from_utf8(writer.as_ref()).unwrap();
+        assert_eq!(
+            output,
+            "\x1b[1;31m        789\x1b[0m m0\n12345678901 m1\n\x1b[1;31m12345679690\x1b[0m total\n"
+        );
+    }
+
+    #[test]
+    fn filename_first() {
+        let opts = ShowOptions {
+            filename_first: true,
+            ..Default::default()
+        };
+        let mut writer = vec![];
+        print_table(&mut writer, &sample_metrics(), &opts);
+        let output = std::str::from_utf8(writer.as_ref()).unwrap();
+        assert_eq!(
+            output,
+            "m0\t789\t1239875670\t123\nm1\t12345678901\t4567890\t1234567890\ntotal\t12345679690\t1244443560\t1234568013\n"
+        )
+    }
+
+    #[test]
+    fn encoding_column() {
+        let opts = ShowOptions {
+            lines: true,
+            encoding: true,
+            ..Default::default()
+        };
+        let all_metrics = [Metrics {
+            lines: 3,
+            encoding: Some("windows-1252".to_owned()),
+            encoding_confident: Some(false),
+            filename: "a".to_owned(),
+            ..Default::default()
+        }];
+        let mut writer = vec![];
+        print_table(&mut writer, &all_metrics, &opts);
+        let output = std::str::from_utf8(writer.as_ref()).unwrap();
+        assert_eq!(output, "       3 windows-1252 uncertain a\n");
+    }
+
+    #[test]
+    fn total_label_and_placement() {
+        let opts = ShowOptions {
+            lines: true,
+            total_label: Some("sum".to_owned()),
+            total_first: true,
+            ..Default::default()
+        };
+        let mut writer = vec![];
+        print_table(&mut writer, &sample_metrics(), &opts);
+        let output = std::str::from_utf8(writer.as_ref()).unwrap();
+        assert_eq!(output, "12345679690 sum\n        789 m0\n12345678901 m1\n")
+    }
+
+    #[test]
+    fn subtotals() {
+        let opts = ShowOptions {
+            lines: true,
+            subtotal_every: Some(2),
+            ..Default::default()
+        };
+        let all_metrics: Vec<Metrics> = (1..=5)
+            .map(|lines| Metrics {
+                lines,
+                filename: format!("f{}", lines),
+                ..Default::default()
+            })
+            .collect();
+        let mut writer = vec![];
+        print_table(&mut writer, &all_metrics, &opts);
+        let output = std::str::from_utf8(writer.as_ref()).unwrap();
+        assert_eq!(
+            output,
+            "       1 f1\n       2 f2\n       3 subtotal\n       3 f3\n       4 f4\n      10 subtotal\n       5 f5\n      15 total\n"
+        )
+    }
+
+    #[test]
+    fn group_by_directory() {
+        let all_metrics: Vec<Metrics> =
+            ["src/main.rs", "README.md", "src/bin/x.rs", "./tests/a.txt"]
+                .iter()
+                .map(|filename| Metrics {
+                    lines: 1,
+                    filename: filename.to_string(),
+                    ..Default::default()
+                })
+                .collect();
+        let grouped = |depth| -> Vec<(String, usize)> {
+            group_by_dir(&all_metrics, depth)
+                .into_iter()
+                .map(|m| (m.filename, m.lines))
+                .collect()
+        };
+        assert_eq!(
+            grouped(1),
+            [
+                ("src".to_owned(), 2),
+                (".".to_owned(), 1),
+                ("tests".to_owned(), 1)
+            ]
+        );
+        assert_eq!(
+            grouped(2),
+            [
+                ("src".to_owned(), 1),
+                (".".to_owned(), 1),
+                ("src/bin".to_owned(), 1),
+                ("tests".to_owned(), 1)
+            ]
+        );
+        let absolute = [Metrics {
+            filename: "/srv/data/a.txt".to_owned(),
+            ..Default::default()
+        }];
+        let grouped = group_by_dir(&absolute, 1);
+        assert_eq!(
+            std::path::Path::new(&grouped[0].filename),
+            std::path::Path::new("/srv")
+        );
+    }
+
+    #[test]
+    fn goal_progress() {
+        assert_eq!(parse_goal("words=50000").unwrap(), (Column::Words, 50000));
+        assert_eq!(parse_goal("bytes=1K").unwrap(), (Column::Bytes, 1024));
+        assert!(parse_goal("words").is_err());
+        assert!(parse_goal("words=0").is_err());
+        let opts = ShowOptions {
+            words: true,
+            goal: Some((Column::Words, 2000)),
+            ..Default::default()
+        };
+        let m = Metrics {
+            words: 1234,
+            filename: "chapter1.md".to_owned(),
+            ..Default::default()
+        };
+        let mut writer = vec![];
+        print_table(&mut writer, &[m], &opts);
+        let output = std::str::from_utf8(writer.as_ref()).unwrap();
+        assert_eq!(output, "    1234  61.7% chapter1.md\n")
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn timeout() {
+        let fifo = TempPath::fifo("timeout");
+        let opts = CountOptions {
+            timeout: Some(std::time::Duration::from_millis(50)),
+            ..Default::default()
+        };
+        let err = count_in_time(fifo.name(), &opts).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn filter_command() {
+        let file = TempPath::file("filter", "<p>one</p>\n<p>two three</p>\n");
+        let filename = file.name();
+        let m = count_filtered(filename, "sed 's/<[^>]*>//g'", &CountOptions::default());
+        let failed = count_filtered(filename, "exit 3", &CountOptions::default());
+        let m = m.unwrap();
+        assert_eq!((m.lines, m.words, m.bytes), (2, 3, 14));
+        assert_eq!(m.filename, filename);
+        assert!(failed.is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn inherited_fd() {
+        use std::os::fd::IntoRawFd;
+
+        let fd = File::open("tests/abc.txt").unwrap().into_raw_fd();
+        let mut input = BufReader::new(console::inherited(fd).unwrap());
+        let m = count_reader(&mut input, "", &CountOptions::default()).unwrap();
+        assert_eq!((m.lines, m.words, m.bytes), (9, 7, 43));
+        assert!(console::inherited(-1).is_err());
+    }
+
+    #[test]
+    fn raw_counts() {
+        let path = "tests/def.txt";
+        let full = count_reader(
+            &mut BufReader::new(File::open(path).unwrap()),
+            path,
+            &CountOptions::default(),
+        )
+        .unwrap();
+        for raw in [RawCounts::Bytes, RawCounts::LinesAndBytes] {
+            let opts = CountOptions::default();
+            let m = count_raw(File::open(path).unwrap(), path, raw, &opts).unwrap();
+            assert_eq!(m.bytes, full.bytes);
+            if raw == RawCounts::LinesAndBytes {
+                assert_eq!(m.lines, full.lines);
+            }
+        }
+        // Decoded after all
+        let utf16 = [0xff, 0xfe, b'a', 0, b'\n', 0, 0x0a, 0x01];
+        let file = TempPath::file("raw", utf16);
+        let f = File::open(&file).unwrap();
+        let m = count_raw(
+            f,
+            "utf16",
+            RawCounts::LinesAndBytes,
+            &CountOptions::default(),
+        );
+        assert_eq!(m.unwrap().lines, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn raw_counts_of_pipe() {
+        let fifo = TempPath::fifo("pipe");
+        let writer = {
+            let fifo = fifo.to_path_buf();
+            std::thread::spawn(move || std::fs::write(fifo, vec![b'x'; 3_000_000]).unwrap())
+        };
+        let f = File::open(&fifo).unwrap();
+        let m = count_raw(f, "fifo", RawCounts::Bytes, &CountOptions::default()).unwrap();
+        writer.join().unwrap();
+        assert_eq!(m.bytes, 3_000_000);
+    }
+
+    // A comparison of the reading paths, not a check:
+    // cargo test --release -- --ignored --nocapture raw_throughput
+    #[cfg(unix)]
+    #[test]
+    #[ignore]
+    fn raw_throughput() {
+        const SIZE: usize = 200_000_000;
+        let line = b"The quick brown fox jumps over the lazy dog, again and again.\n";
+        let text: Vec<u8> = line.iter().copied().cycle().take(SIZE).collect();
+        let text = std::sync::Arc::new(text);
+        let through_fifo = |name: &str, count: &dyn Fn(File) -> Metrics| {
+            let fifo = TempPath::fifo("bench");
+            let writer = {
+                let (fifo, text) = (fifo.to_path_buf(), std::sync::Arc::clone(&text));
+                std::thread::spawn(move || std::fs::write(fifo, &*text).unwrap())
+            };
+            let start = std::time::Instant::now();
+            let m = count(File::open(&fifo).unwrap());
+            let elapsed = start.elapsed();
+            writer.join().unwrap();
+            assert_eq!(m.bytes, SIZE);
+            println!("{:<28} {:>8.2} s", name, elapsed.as_secs_f64());
+        };
+        let opts = CountOptions::default();
+        through_fifo("BufReader, all counts", &|f| {
+            count_reader(&mut BufReader::with_capacity(1024, f), "fifo", &opts).unwrap()
+        });
+        through_fifo("raw lines and bytes", &|f| {
+            count_raw(f, "fifo", RawCounts::LinesAndBytes, &opts).unwrap()
+        });
+        through_fifo("raw bytes (splice on Linux)", &|f| {
+            count_raw(f, "fifo", RawCounts::Bytes, &opts).unwrap()
+        });
+    }
+
+    #[test]
+    fn count_in_chunks() {
+        let text = "ab\u{e4}c  d\te\nfg\u{4e2d} h\n".as_bytes();
+        let whole = count_reader(&mut &text[..], "whole", &CountOptions::default()).unwrap();
+        for split in 0..text.len() {
+            let mut counter = Counter::new("split", &CountOptions::default());
+            counter.update(&text[..split]).unwrap();
+            counter.update(&text[split..]).unwrap();
+            let m = counter.finish().unwrap();
+            assert_eq!(
+                (m.bytes, m.chars, m.lines, m.words, m.max_line_length),
+                (19, 16, 2, 5, 9)
+            );
+            assert_eq!(m.words, whole.words);
+        }
+    }
+
+    #[test]
+    fn hidden_files() {
+        assert!(is_hidden(".gitignore"));
+        assert!(is_hidden(".github/workflows/ci.yml"));
+        assert!(is_hidden("src/.hidden/main.rs"));
+        assert!(!is_hidden("src/main.rs"));
+        assert!(!is_hidden("./src/main.rs"));
+        assert!(!is_hidden("../wc/src/main.rs"));
+    }
+
+    #[test]
+    fn combined_stream() {
+        // A word continued across the file boundary is counted once
+        let mut counter = Counter::new("total", &CountOptions::default());
+        feed(&mut counter, &mut &b"one tw"[..]).unwrap();
+        feed(&mut counter, &mut &b"o three\n"[..]).unwrap();
+        let m = counter.finish().unwrap();
+        assert_eq!((m.lines, m.words, m.bytes), (1, 3, 14));
+    }
+
+    #[test]
+    fn csv_records() {
+        let opts = CountOptions {
+            records: Some(RecordFormat::Csv),
+            ..Default::default()
+        };
+        let text = "id,name,note\n1,a,\"two\nlines\"\n2,b,\"with, comma\"\n\n3,c,last";
+        let whole = count_reader(&mut text.as_bytes(), "whole", &opts).unwrap();
+        assert_eq!((whole.lines, whole.records, whole.fields), (5, 4, 3));
+        for split in 0..text.len() {
+            let mut counter = Counter::new("split", &opts);
+            counter.update(&text.as_bytes()[..split]).unwrap();
+            counter.update(&text.as_bytes()[split..]).unwrap();
+            let m = counter.finish().unwrap();
+            assert_eq!((m.records, m.fields), (whole.records, whole.fields));
+        }
+        let opts = CountOptions {
+            records: Some(RecordFormat::Tsv),
+            ..Default::default()
+        };
+        let m = count_reader(&mut &b"a\tb\n\"c\td\te\n"[..], "tsv", &opts).unwrap();
+        assert_eq!((m.records, m.fields), (2, 3));
+    }
+
+    #[test]
+    fn json_documents() {
+        let opts = CountOptions {
+            json_documents: true,
+            ..Default::default()
+        };
+        let count = |json: &str| {
+            let m = count_reader(&mut json.as_bytes(), "json", &opts).unwrap();
+            (m.documents, m.invalid_documents)
+        };
+        assert_eq!(count("{\"a\": 1}\n\n[2, 3]\n{\"broken\n\"last\""), (3, 1));
+        assert_eq!(count("  [\n  {\"a\": 1},\n  2,\n  \"three\"\n]\n"), (3, 0));
+        assert_eq!(count("[1, 2"), (0, 1));
+        assert_eq!(count(""), (0, 0));
+    }
+
+    #[test]
+    fn markdown_words() {
+        let opts = CountOptions {
+            markup: Some(markup::Format::Markdown),
+            ..Default::default()
+        };
+        let text = "---\ntags: [a, b]\n---\n## Intro\n\nSome *prose* - with a [link](https://x.org).\n```\nlet code = 1;\n```\n";
+        let m = count_reader(&mut text.as_bytes(), "md", &opts).unwrap();
+        assert_eq!((m.lines, m.words), (9, 7));
+    }
+
+    #[test]
+    fn mbox_messages() {
+        let opts = CountOptions {
+            markup: Some(markup::Format::Mbox),
+            ..Default::default()
+        };
+        let mbox =
+            "From a@example.com Mon Jan  1 00:00:00 2024\nSubject: one two\n\nThree four.\n\n\
+                    From b@example.com Tue Jan  2 00:00:00 2024\nSubject: five\n\nSix\n";
+        let m = count_reader(&mut mbox.as_bytes(), "mbox", &opts).unwrap();
+        assert_eq!((m.messages, m.lines, m.words), (2, 3, 3));
+    }
+
+    #[test]
+    fn split_sections() {
+        let path = TempPath::file(
+            "sections.txt",
+            "Title page\nCHAPTER 1\nIt was a dark night.\nCHAPTER 2\nThe end.\n",
+        );
+        let filename = path.name();
+        let pattern = regex::Regex::new("CHAPTER").unwrap();
+        let sections = count_sections(filename, &pattern, &CountOptions::default()).unwrap();
+        let counts: Vec<(String, usize, usize)> = sections
+            .iter()
+            .map(|m| (m.filename.replacen(filename, "f", 1), m.lines, m.words))
+            .collect();
+        assert_eq!(
+            counts,
+            [
+                ("f: (start)".to_owned(), 1, 2),
+                ("f: CHAPTER 1".to_owned(), 2, 7),
+                ("f: CHAPTER 2".to_owned(), 2, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn per_line_counts() {
+        let opts = ShowOptions::default();
+        let count_opts = CountOptions {
+            per_line: true,
+            ..Default::default()
+        };
+        let mut writer = vec![];
+        let mut input = &b"one two\r\n\n\tthree\n"[..];
+        print_lines(&mut writer, &mut input, "f", &opts, &count_opts).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            "      1       2       7       7 f\n      2       0       0       0 f\n      3       1       6      13 f\n"
+        );
+
+        // The byte order mark is only at the start of the input
+        let utf16: Vec<u8> = "\u{feff}a b\nc\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let mut writer = vec![];
+        print_lines(&mut writer, &mut &utf16[..], "f", &opts, &count_opts).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            "      1       2       3       3 f\n      2       1       1       1 f\n"
+        );
+    }
+
+    #[test]
+    fn field_counts() {
+        let opts = CountOptions {
+            records: Some(RecordFormat::Delimited(b';')),
+            ..Default::default()
+        };
+        let data = "a;b;c\n1;2;3\n4;\"5;6\"\n7;8;9\n10\n";
+        let m = count_reader(&mut data.as_bytes(), "data", &opts).unwrap();
+        assert_eq!(
+            (m.records, m.min_fields, m.modal_fields, m.fields),
+            (5, 1, 3, 3)
+        );
+    }
+
+    #[test]
+    fn skip_front_matter() {
+        let opts = CountOptions {
+            skip_front_matter: true,
+            ..Default::default()
+        };
+        let post = "---\ntitle: A post\ntags: [a, b]\n---\nThe body.\n";
+        let m = count_reader(&mut post.as_bytes(), "post", &opts).unwrap();
+        assert_eq!((m.lines, m.words, m.bytes), (1, 2, 10));
+    }
+
+    #[test]
+    fn line_range() {
+        let opts = CountOptions {
+            line_range: Some(Range {
+                start: Some(2),
+                end: Some(3),
+            }),
+            ..Default::default()
+        };
+        let text = "one\ntwo three\nfour\nfive six\n";
+        let m = count_reader(&mut text.as_bytes(), "text", &opts).unwrap();
+        assert_eq!((m.lines, m.words, m.bytes), (2, 3, 15));
+    }
+
+    #[test]
+    fn byte_range() {
+        let path = TempPath::file("byte-range", "one\ntwo three\nfour\n");
+        let opts = CountOptions {
+            byte_range: Some(Range {
+                start: Some(4),
+                end: Some(13),
+            }),
+            ..Default::default()
+        };
+        let m = count(path.name(), &opts).unwrap();
+        assert_eq!((m.lines, m.words, m.bytes), (0, 2, 9));
+        let m = count_reader(&mut &b"one\ntwo three\nfour\n"[..], "pipe", &opts).unwrap();
+        assert_eq!((m.lines, m.words, m.bytes), (0, 2, 9));
+    }
+
+    #[test]
+    fn tail() {
+        let opts = CountOptions {
+            tail: Some(2),
+            ..Default::default()
+        };
+        let text = "one\ntwo three\nfour\nfive six\n";
+        let m = count_reader(&mut text.as_bytes(), "text", &opts).unwrap();
+        assert_eq!((m.lines, m.words, m.bytes), (2, 3, 14));
+        let path = TempPath::file("tail", text);
+        let m = count(path.name(), &opts).unwrap();
+        assert_eq!((m.lines, m.words, m.bytes), (2, 3, 14));
+    }
+
+    #[test]
+    fn ignore_lines() {
+        let opts = CountOptions {
+            ignore_lines: Some(regex::Regex::new("#").unwrap()),
+            tail: Some(2),
+            ..Default::default()
+        };
+        let config = "# settings\nname = wc\n# the size\nsize = 10\ncolor = auto\n";
+        let m = count_reader(&mut config.as_bytes(), "config", &opts).unwrap();
+        assert_eq!((m.lines, m.words, m.bytes), (2, 6, 23));
+    }
+
+    #[test]
+    fn match_lines() {
+        let opts = CountOptions {
+            match_lines: Some(regex::Regex::new("ERROR").unwrap()),
+            ..Default::default()
+        };
+        let log = "12:00 INFO started\n12:01 ERROR disk full\n12:02 ERROR failed";
+        let m = count_reader(&mut log.as_bytes(), "log", &opts).unwrap();
+        assert_eq!((m.lines, m.words, m.chars), (1, 7, 40));
+    }
+
+    #[test]
+    fn content_hash() {
+        let opts = CountOptions {
+            hash: true,
+            ..Default::default()
+        };
+        let hash = |text: &str| {
+            count_reader(&mut text.as_bytes(), "text", &opts)
+                .unwrap()
+                .hash
+        };
+        assert_eq!(hash("one two\n"), hash("one two\n"));
+        assert_ne!(hash("one two\n"), hash("two one\n"));
+        assert_eq!(
+            count_reader(&mut &b""[..], "text", &CountOptions::default())
+                .unwrap()
+                .hash,
+            None
+        );
+    }
+
+    #[test]
+    fn log_levels() {
+        let opts = CountOptions {
+            log_levels: vec!["ERROR".to_owned(), "WARN".to_owned()],
+            ..Default::default()
+        };
+        let log = "12:00 INFO started\n12:01 WARNING disk\n12:02 ERROR failed\n12:03 ERROR again";
+        let m = count_reader(&mut log.as_bytes(), "log", &opts).unwrap();
+        assert_eq!(m.log_levels, [2, 1]);
+        assert_eq!(m.lines, 3);
+    }
+
+    #[test]
+    fn count_utf16() {
+        let le = b"\xff\xfea\0 \0=\xd8\x00\xde\n\0";
+        let be = b"\xfe\xff\0a\0 \xd8=\xde\x00\0\n";
+        for text in &[&le[..], &be[..]] {
+            for split in 0..text.len() {
+                let mut counter = Counter::new("split", &CountOptions::default());
+                counter.update(&text[..split]).unwrap();
+                counter.update(&text[split..]).unwrap();
+                let m = counter.finish().unwrap();
+                assert_eq!((m.bytes, m.chars, m.lines, m.words), (12, 4, 1, 2));
+            }
+        }
+        let strict = CountOptions {
+            invalid: InvalidInput::Strict,
+            ..Default::default()
+        };
+        let mut counter = Counter::new("unpaired surrogate", &strict);
+        assert!(counter.update(b"\xff\xfe\x00\xdca\0").is_err());
+        let mut counter = Counter::new("odd length", &strict);
+        counter.update(b"\xff\xfea\0b").unwrap();
+        assert!(counter.finish().is_err());
+        let m = count_reader(&mut &b"a"[..], "single byte", &CountOptions::default()).unwrap();
+        assert_eq!((m.bytes, m.chars, m.words), (1, 1, 1));
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let text = b"a\xffb \xe4\xb8 c\xe4";
+        let results = [
+            (InvalidInput::Ignore, (9, 5, 2)),
+            (InvalidInput::Replace, (9, 8, 3)),
+        ];
+        for (invalid, expected) in &results {
+            let opts = CountOptions {
+                invalid: *invalid,
+                ..Default::default()
+            };
+            for split in 0..text.len() {
+                let mut counter = Counter::new("split", &opts);
+                counter.update(&text[..split]).unwrap();
+                counter.update(&text[split..]).unwrap();
+                let m = counter.finish().unwrap();
+                assert_eq!((m.bytes, m.chars, m.words), *expected);
+            }
+        }
+        let strict = CountOptions {
+            invalid: InvalidInput::Strict,
+            ..Default::default()
+        };
+        assert!(count_reader(&mut &text[..], "strict", &strict).is_err());
+    }
+
+    #[test]
+    fn unicode_words() {
+        let text = "The quick (\u{201c}brown\u{201d}) fox can't jump 32.3 feet, right?\n\u{4e2d}\u{6587} - - -\n";
+        let unicode = CountOptions {
+            words: WordMode::Unicode,
+            ..Default::default()
+        };
+        let m = count_reader(&mut text.as_bytes(), "unicode", &unicode).unwrap();
+        assert_eq!(m.words, 11);
+        let m = count_reader(&mut text.as_bytes(), "whitespace", &CountOptions::default()).unwrap();
+        assert_eq!(m.words, 13);
+    }
+
+    #[test]
+    fn word_delimiters() {
+        let opts = CountOptions {
+            word_delimiters: vec![':', ','],
+            ..Default::default()
+        };
+        let m = count_reader(&mut &b"/usr/bin:/bin::/sbin a,b c\n"[..], "path", &opts).unwrap();
+        assert_eq!(m.words, 6);
+    }
+
+    #[test]
+    fn display_width() {
+        // Wide CJK, fullwidth and halfwidth forms, a combining accent and an ambiguous width character
+        let text = "\u{4e2d}\u{6587}\u{ff21}\u{ff71}e\u{301}\u{a7}\n";
+        let m = count_reader(&mut text.as_bytes(), "narrow", &CountOptions::default()).unwrap();
+        assert_eq!(m.max_line_length, 9);
+        let wide = CountOptions {
+            ambiguous_wide: true,
+            ..Default::default()
+        };
+        let m = count_reader(&mut text.as_bytes(), "wide", &wide).unwrap();
+        assert_eq!(m.max_line_length, 10);
+    }
+
+    #[test]
+    fn strip_ansi() {
+        let text = "\x1b[1;31merror\x1b[0m: \x1b]0;title\x07failed \x1b(Bhere\n";
+        let opts = CountOptions {
+            strip_ansi: true,
+            ..Default::default()
+        };
+        let m = count_reader(&mut text.as_bytes(), "colors", &opts).unwrap();
+        assert_eq!(
+            (m.chars, m.words, m.lines, m.max_line_length),
+            (19, 3, 1, 18)
+        );
+        assert_eq!(m.bytes, text.len());
+    }
+
+    #[test]
+    fn tab_width() {
+        let text = b"\tab\tc\n";
+        let m = count_reader(&mut &text[..], "default", &CountOptions::default()).unwrap();
+        assert_eq!(m.max_line_length, 17);
+        let opts = CountOptions {
+            tab_width: 4,
+            ..Default::default()
+        };
+        let m = count_reader(&mut &text[..], "four", &opts).unwrap();
+        assert_eq!(m.max_line_length, 9);
+    }
+
+    #[test]
+    fn char_units() {
+        let text = "a\u{e4}\u{1f600}\n".as_bytes();
+        let results = [
+            (CharUnit::Scalars, 4),
+            (CharUnit::Utf16, 5),
+            (CharUnit::Bytes, 8),
+        ];
+        for (unit, chars) in &results {
+            let opts = CountOptions {
+                chars_as: *unit,
+                ..Default::default()
+            };
+            let m = count_reader(&mut &text[..], "units", &opts).unwrap();
+            assert_eq!(m.chars, *chars);
+        }
+    }
+
+    #[test]
+    fn emoji_width() {
+        // Widths as shown by terminals that render emoji sequences as one glyph
+        let expected = [9, 11, 8, 7, 8, 8, 10];
+        let fixture = include_str!("../tests/emoji.txt");
+        assert_eq!(fixture.lines().count(), expected.len());
+        for (line, width) in fixture.lines().zip(&expected) {
+            let m = count_reader(&mut line.as_bytes(), line, &CountOptions::default()).unwrap();
+            assert_eq!(m.max_line_length, *width, "{}", line);
+        }
+    }
+
+    #[test]
+    fn grapheme_clusters() {
+        let text = "e\u{301}\u{1f44d}\u{1f3fd} \u{1f1e9}\u{1f1ea}\r\n\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}\n";
+        let opts = CountOptions {
+            graphemes: true,
+            ..Default::default()
+        };
+        let m = count_reader(&mut text.as_bytes(), "graphemes", &opts).unwrap();
+        assert_eq!(m.graphemes, 7);
+        assert_eq!(m.chars, 15);
+    }
+
+    #[test]
+    fn digit_grouping() {
+        let western = NumberFormat {
+            separator: ",".to_owned(),
+            indian: false,
+        };
+        assert_eq!(western.format(0), "0");
+        assert_eq!(western.format(999), "999");
+        assert_eq!(western.format(1000), "1,000");
+        assert_eq!(western.format(1_234_567), "1,234,567");
+        assert_eq!(western.width(7), 9);
+        let german = NumberFormat {
+            separator: ".".to_owned(),
+            indian: false,
+        };
+        assert_eq!(german.format(123_456), "123.456");
+        let indian = NumberFormat {
+            separator: ",".to_owned(),
+            indian: true,
+        };
+        assert_eq!(indian.format(1234), "1,234");
+        assert_eq!(indian.format(1_234_567), "12,34,567");
+        assert_eq!(indian.format(123_456_789), "12,34,56,789");
+        assert_eq!(indian.width(9), 12);
     }
 }
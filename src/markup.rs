@@ -0,0 +1,969 @@
+//
+// Filters that remove markup from text before it is counted, so the counts reflect the prose.
+// Text is filtered a line at a time; line breaks themselves are kept, except for mail headers,
+// which are left out entirely.
+//
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Markdown,
+    Html,
+    Latex,
+    Subtitles,
+    Mbox,
+}
+
+impl Format {
+    // Formats recognized by the file extension
+    pub fn for_file(filename: &str) -> Option<Format> {
+        let extension = std::path::Path::new(filename).extension()?.to_str()?;
+        if extension.eq_ignore_ascii_case("srt") || extension.eq_ignore_ascii_case("vtt") {
+            Some(Format::Subtitles)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Filter {
+    Markdown(Markdown),
+    Html(Html),
+    Latex(Latex),
+    Subtitles(Subtitles),
+    Mbox(Mbox),
+}
+
+impl Filter {
+    pub fn new(format: Format) -> Filter {
+        match format {
+            Format::Markdown => Filter::Markdown(Markdown::default()),
+            Format::Html => Filter::Html(Html::default()),
+            Format::Latex => Filter::Latex(Latex::default()),
+            Format::Subtitles => Filter::Subtitles(Subtitles::default()),
+            Format::Mbox => Filter::Mbox(Mbox::default()),
+        }
+    }
+
+    // The text of a line without its markup, or None if the line is left out with its line break
+    pub fn line(&mut self, line: &str) -> Option<String> {
+        match self {
+            Filter::Markdown(markdown) => Some(markdown.line(line)),
+            Filter::Html(html) => Some(html.line(line)),
+            Filter::Latex(latex) => Some(latex.line(line)),
+            Filter::Subtitles(subtitles) => Some(subtitles.line(line)),
+            Filter::Mbox(mbox) => mbox.line(line),
+        }
+    }
+
+    // Number of subtitle cues so far
+    pub fn cues(&self) -> usize {
+        match self {
+            Filter::Subtitles(subtitles) => subtitles.cues,
+            _ => 0,
+        }
+    }
+
+    // Number of mail messages so far
+    pub fn messages(&self) -> usize {
+        match self {
+            Filter::Mbox(mbox) => mbox.messages,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Markdown {
+    lines: usize,
+    // Closing delimiter of the YAML (---) or TOML (+++) front matter we are in
+    front_matter: Option<&'static str>,
+    // Character and length of the fence of the code block we are in
+    fence: Option<(char, usize)>,
+}
+
+// The fence character and its length, if the line is a code fence
+fn fence(line: &str) -> Option<(char, usize)> {
+    let line = line.trim_start();
+    let c = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = line.chars().take_while(|x| *x == c).count();
+    if len >= 3 {
+        Some((c, len))
+    } else {
+        None
+    }
+}
+
+// A thematic break like --- or * * *, or a setext heading underline like ===
+fn is_rule(line: &str) -> bool {
+    let markers: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    markers.len() >= 3
+        && ["-*_", "="]
+            .iter()
+            .any(|set| set.contains(markers[0]) && markers.iter().all(|c| *c == markers[0]))
+}
+
+// A link reference definition like [id]: https://example.com "Title"
+fn is_link_definition(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with('[') && line.find("]:").is_some_and(|end| end > 1)
+}
+
+// Remove block quote markers, heading markers, list markers and task list boxes
+fn strip_block_markers(mut line: &str) -> &str {
+    loop {
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix('>') {
+            line = rest;
+        } else {
+            line = trimmed;
+            break;
+        }
+    }
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&hashes) && line[hashes..].starts_with([' ', '\t']) {
+        // The closing sequence of an ATX heading is optional
+        return line[hashes..].trim().trim_end_matches('#');
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let marker = if line.starts_with(['-', '*', '+']) {
+        1
+    } else if (1..=9).contains(&digits) && line[digits..].starts_with(['.', ')']) {
+        digits + 1
+    } else {
+        0
+    };
+    if marker > 0 && line[marker..].starts_with([' ', '\t']) {
+        line = line[marker..].trim_start();
+        for checkbox in ["[ ] ", "[x] ", "[X] "] {
+            if let Some(rest) = line.strip_prefix(checkbox) {
+                return rest;
+            }
+        }
+    }
+    line
+}
+
+// Index after the closing bracket matching the opening one at start, if there is one
+fn closing(chars: &[char], start: usize, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in chars.iter().enumerate().skip(start) {
+        if *c == open {
+            depth += 1;
+        } else if *c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i + 1);
+            }
+        }
+    }
+    None
+}
+
+// Keep the text of links and images, drop their destinations and emphasis markers
+fn strip_inline_markup(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let is_word = |i: Option<&char>| i.is_some_and(|c| c.is_alphanumeric());
+    let mut text = String::with_capacity(line.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '!' if chars.get(i + 1) == Some(&'[') => (),
+            '[' | '*' | '`' | '~' => (),
+            ']' => {
+                // [text](destination "title") or [text][reference]
+                let destination = match chars.get(i + 1) {
+                    Some('(') => closing(&chars, i + 1, '(', ')'),
+                    Some('[') => closing(&chars, i + 1, '[', ']'),
+                    _ => None,
+                };
+                if let Some(end) = destination {
+                    i = end;
+                    continue;
+                }
+            }
+            '<' => {
+                // Autolinks like <https://example.com>
+                let rest: String = chars[i + 1..].iter().take(8).collect();
+                let end = chars[i..].iter().position(|c| *c == '>');
+                if let (true, Some(end)) = (
+                    ["http://", "https://", "mailto:"]
+                        .iter()
+                        .any(|scheme| rest.starts_with(scheme)),
+                    end,
+                ) {
+                    i += end + 1;
+                    continue;
+                }
+                text.push(c);
+            }
+            // Underscores within words, as in snake_case, are not emphasis
+            '_' if !(is_word(i.checked_sub(1).and_then(|p| chars.get(p)))
+                && is_word(chars.get(i + 1))) => {}
+            '|' => text.push(' '),
+            c => text.push(c),
+        }
+        i += 1;
+    }
+    text
+}
+
+impl Markdown {
+    fn line(&mut self, line: &str) -> String {
+        self.lines += 1;
+        let trimmed = line.trim_end();
+        if let Some(end) = self.front_matter {
+            if trimmed == end || (end == "---" && trimmed == "...") {
+                self.front_matter = None;
+            }
+            return String::new();
+        }
+        if self.lines == 1 && (trimmed == "---" || trimmed == "+++") {
+            self.front_matter = Some(if trimmed == "---" { "---" } else { "+++" });
+            return String::new();
+        }
+        if let Some((c, len)) = self.fence {
+            if fence(line).is_some_and(|(x, n)| x == c && n >= len)
+                && trimmed.trim_start().chars().all(|x| x == c)
+            {
+                self.fence = None;
+            }
+            return String::new();
+        }
+        if let Some(fence) = fence(line) {
+            self.fence = Some(fence);
+            return String::new();
+        }
+        if is_rule(line) || is_link_definition(line) {
+            return String::new();
+        }
+        strip_inline_markup(strip_block_markers(line))
+    }
+}
+
+// Where we are in an HTML document
+#[derive(Clone, Debug, Default, PartialEq)]
+enum HtmlState {
+    #[default]
+    Text,
+    // The name read so far and the quote of the attribute value we are in
+    Tag(String, Option<char>),
+    Comment,
+    // Content of a script or style element, up to its closing tag
+    Raw(&'static str),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Html {
+    state: HtmlState,
+    // The end of a comment or of the raw text read so far, to find where they end
+    tail: String,
+}
+
+// Elements that separate the text before and after them
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "td",
+    "th",
+    "tr",
+    "ul",
+];
+
+// Text of a character reference without & and ;
+fn entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "shy" => '\u{ad}',
+        "ndash" => '\u{2013}',
+        "mdash" => '\u{2014}',
+        "lsquo" => '\u{2018}',
+        "rsquo" => '\u{2019}',
+        "ldquo" => '\u{201c}',
+        "rdquo" => '\u{201d}',
+        "hellip" => '\u{2026}',
+        "copy" => '\u{a9}',
+        "reg" => '\u{ae}',
+        "trade" => '\u{2122}',
+        "euro" => '\u{20ac}',
+        _ => return None,
+    })
+}
+
+// Replace character references like &amp; or &#8212; by the characters they stand for
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let c = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 32)
+            .and_then(|end| entity(&rest[1..end + 1]).map(|c| (c, end + 2)));
+        match c {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+impl Html {
+    fn line(&mut self, line: &str) -> String {
+        let mut text = String::new();
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match &mut self.state {
+                HtmlState::Text if c == '<' => match chars.peek() {
+                    Some(next) if next.is_ascii_alphabetic() || matches!(next, '/' | '!' | '?') => {
+                        self.state = HtmlState::Tag(String::new(), None)
+                    }
+                    _ => text.push(c),
+                },
+                HtmlState::Text => text.push(c),
+                HtmlState::Tag(name, Some(quote)) => {
+                    if c == *quote {
+                        self.state = HtmlState::Tag(std::mem::take(name), None);
+                    }
+                }
+                HtmlState::Tag(name, None) => match c {
+                    '"' | '\'' => self.state = HtmlState::Tag(std::mem::take(name), Some(c)),
+                    '>' => {
+                        let name = name.to_ascii_lowercase();
+                        let name = name.split(' ').next().unwrap_or_default();
+                        if BLOCK_ELEMENTS.contains(&name.trim_start_matches('/')) {
+                            text.push(' ');
+                        }
+                        self.state = match name {
+                            "script" => HtmlState::Raw("</script"),
+                            "style" => HtmlState::Raw("</style"),
+                            _ => HtmlState::Text,
+                        };
+                    }
+                    c if c.is_whitespace() => name.push(' '),
+                    c if !name.contains(' ') => {
+                        name.push(c);
+                        if name == "!--" {
+                            self.state = HtmlState::Comment;
+                            self.tail.clear();
+                        }
+                    }
+                    _ => (),
+                },
+                HtmlState::Comment => {
+                    self.tail.push(c);
+                    if self.tail.ends_with("-->") {
+                        self.state = HtmlState::Text;
+                        self.tail.clear();
+                    }
+                }
+                HtmlState::Raw(end) => {
+                    self.tail.push(c.to_ascii_lowercase());
+                    if self.tail.ends_with(*end) {
+                        self.state = HtmlState::Tag(end[1..].to_owned(), None);
+                        self.tail.clear();
+                    }
+                }
+            }
+            // Only the end of what was skipped matters
+            if self.tail.len() > 64 {
+                self.tail.drain(..self.tail.len() - 16);
+            }
+        }
+        decode_entities(&text)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Latex {
+    // Between \documentclass and \begin{document}
+    preamble: bool,
+    // The end of the math or verbatim text we are in, e.g. $ or \end{align*}
+    skip_until: Option<String>,
+    // Brace depth in an argument that is left out
+    dropped: usize,
+}
+
+// Environments whose content is not prose
+const SKIPPED_ENVIRONMENTS: &[&str] = &[
+    "align",
+    "align*",
+    "alignat",
+    "alignat*",
+    "comment",
+    "displaymath",
+    "eqnarray",
+    "eqnarray*",
+    "equation",
+    "equation*",
+    "gather",
+    "gather*",
+    "lstlisting",
+    "math",
+    "minted",
+    "multline",
+    "multline*",
+    "verbatim",
+    "verbatim*",
+];
+
+// Commands whose arguments are not prose; of \href only the URL is left out
+const DROPPED_ARGUMENTS: &[&str] = &[
+    "autoref",
+    "bibliography",
+    "bibliographystyle",
+    "cite",
+    "citep",
+    "citet",
+    "cref",
+    "Cref",
+    "end",
+    "eqref",
+    "hspace",
+    "href",
+    "include",
+    "includegraphics",
+    "input",
+    "label",
+    "newcommand",
+    "pageref",
+    "ref",
+    "renewcommand",
+    "setlength",
+    "url",
+    "usepackage",
+    "vspace",
+];
+
+impl Latex {
+    fn line(&mut self, line: &str) -> String {
+        let mut text = String::new();
+        let mut rest = line;
+        while !rest.is_empty() {
+            if let Some(end) = &self.skip_until {
+                match rest.find(end.as_str()) {
+                    Some(position) => {
+                        rest = &rest[position + end.len()..];
+                        self.skip_until = None;
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+            if self.preamble {
+                match rest.find("\\begin{document}") {
+                    Some(position) => {
+                        rest = &rest[position + "\\begin{document}".len()..];
+                        self.preamble = false;
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+            let c = rest.chars().next().unwrap_or_default();
+            rest = &rest[c.len_utf8()..];
+            if self.dropped > 0 {
+                match c {
+                    '{' => self.dropped += 1,
+                    '}' => self.dropped -= 1,
+                    _ => (),
+                }
+                continue;
+            }
+            match c {
+                // A comment lasts until the end of the line
+                '%' => break,
+                '$' => {
+                    self.skip_until = Some(match rest.strip_prefix('$') {
+                        Some(display) => {
+                            rest = display;
+                            "$$".to_owned()
+                        }
+                        None => "$".to_owned(),
+                    })
+                }
+                '{' | '}' => (),
+                '~' => text.push(' '),
+                '\\' => rest = self.command(rest, &mut text),
+                c => text.push(c),
+            }
+        }
+        text
+    }
+
+    // Handle the command after a backslash, returns what follows it
+    fn command<'a>(&mut self, rest: &'a str, text: &mut String) -> &'a str {
+        let len = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        if len == 0 {
+            // A control symbol like \% or \\
+            let c = match rest.chars().next() {
+                Some(c) => c,
+                None => return rest,
+            };
+            match c {
+                '(' => self.skip_until = Some("\\)".to_owned()),
+                '[' => self.skip_until = Some("\\]".to_owned()),
+                '%' | '&' | '$' | '#' | '_' | '{' | '}' => text.push(c),
+                '\\' | ',' | ';' | ':' | ' ' => text.push(' '),
+                _ => (),
+            }
+            return &rest[c.len_utf8()..];
+        }
+        let name = &rest[..len];
+        let mut rest = rest[len..].strip_prefix('*').unwrap_or(&rest[len..]);
+        // Optional arguments
+        while let Some(end) = rest.strip_prefix('[').and_then(|r| r.find(']')) {
+            rest = &rest[end + 2..];
+        }
+        if name == "documentclass" {
+            self.preamble = true;
+            return "";
+        }
+        if name == "begin" {
+            let environment = rest
+                .strip_prefix('{')
+                .and_then(|r| r.find('}').map(|end| &r[..end]));
+            if let Some(environment) = environment {
+                rest = &rest[environment.len() + 2..];
+                if SKIPPED_ENVIRONMENTS.contains(&environment) {
+                    self.skip_until = Some(format!("\\end{{{}}}", environment));
+                }
+            }
+        } else if DROPPED_ARGUMENTS.contains(&name) {
+            if let Some(argument) = rest.strip_prefix('{') {
+                self.dropped = 1;
+                rest = argument;
+            }
+        }
+        rest
+    }
+}
+
+// Where we are in a SubRip (srt) or WebVTT (vtt) file
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum SubtitleState {
+    // Before a cue: sequence numbers, cue identifiers and blank lines
+    #[default]
+    Between,
+    // The text of a cue, up to the next blank line
+    Cue,
+    // The WEBVTT header or a NOTE, STYLE or REGION block, up to the next blank line
+    Block,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Subtitles {
+    state: SubtitleState,
+    cues: usize,
+}
+
+// Remove tags like <i> or <v Speaker> and SSA overrides like {\an8} from cue text
+fn strip_cue_markup(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find(['<', '{']) {
+        let close = if rest[start..].starts_with('<') {
+            '>'
+        } else {
+            '}'
+        };
+        let end = match rest[start..].find(close) {
+            Some(end) if close == '>' || rest[start..].starts_with("{\\") => start + end,
+            _ => {
+                text.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+                continue;
+            }
+        };
+        text.push_str(&rest[..start]);
+        rest = &rest[end + 1..];
+    }
+    text.push_str(rest);
+    decode_entities(&text)
+}
+
+impl Subtitles {
+    fn line(&mut self, line: &str) -> String {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            self.state = SubtitleState::Between;
+            return String::new();
+        }
+        match self.state {
+            SubtitleState::Block => String::new(),
+            SubtitleState::Cue => strip_cue_markup(line),
+            SubtitleState::Between => {
+                let keyword = trimmed.split_whitespace().next().unwrap_or_default();
+                if trimmed.contains("-->") {
+                    self.cues += 1;
+                    self.state = SubtitleState::Cue;
+                } else if ["WEBVTT", "NOTE", "STYLE", "REGION"]
+                    .contains(&keyword.trim_start_matches('\u{feff}'))
+                {
+                    self.state = SubtitleState::Block;
+                }
+                String::new()
+            }
+        }
+    }
+}
+
+// Messages of an mbox mail archive; only their bodies are text
+#[derive(Clone, Debug)]
+pub struct Mbox {
+    messages: usize,
+    in_headers: bool,
+    // A "From " line only starts a message at the beginning or after a blank line
+    previous_blank: bool,
+}
+
+impl Default for Mbox {
+    fn default() -> Self {
+        Mbox {
+            messages: 0,
+            in_headers: false,
+            previous_blank: true,
+        }
+    }
+}
+
+impl Mbox {
+    fn line(&mut self, line: &str) -> Option<String> {
+        let blank = line.trim().is_empty();
+        let starts_message = self.previous_blank && line.starts_with("From ");
+        self.previous_blank = blank;
+        if starts_message {
+            self.messages += 1;
+            self.in_headers = true;
+            return None;
+        }
+        if self.in_headers {
+            // The headers end with the first blank line
+            self.in_headers = !blank;
+            return None;
+        }
+        // ">From " in a body is escaped, as are the lines that already started with '>' (mboxrd)
+        match line.strip_prefix('>') {
+            Some(unescaped) if unescaped.trim_start_matches('>').starts_with("From ") => {
+                Some(unescaped.to_owned())
+            }
+            _ => Some(line.to_owned()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+enum FrontMatterState {
+    #[default]
+    Start,
+    // In front matter ending with this fence
+    Inside(&'static [u8]),
+    Done,
+}
+
+//
+// Leaves out a YAML (---) or TOML (+++) front matter block at the start of raw input,
+// including its fences, so that it is not counted at all.
+// Front matter that is never closed is not front matter, and is kept.
+//
+#[derive(Clone, Debug, Default)]
+pub struct FrontMatter {
+    state: FrontMatterState,
+    // Input held back while it may still be front matter
+    held: Vec<u8>,
+}
+
+impl FrontMatter {
+    // The part of the input that is not front matter
+    pub fn filter(&mut self, input: &[u8]) -> Vec<u8> {
+        if let FrontMatterState::Done = self.state {
+            return input.to_vec();
+        }
+        let mut start = 0;
+        while let Some(end) = input[start..].iter().position(|b| *b == b'\n') {
+            let line_start = self.held.len();
+            self.held.extend_from_slice(&input[start..start + end + 1]);
+            start += end + 1;
+            let line = &self.held[line_start..];
+            let fence = line
+                .strip_prefix(b"\xef\xbb\xbf")
+                .unwrap_or(line)
+                .trim_ascii_end();
+            match self.state {
+                FrontMatterState::Start => {
+                    if fence == b"---" || fence == b"+++" {
+                        self.state =
+                            FrontMatterState::Inside(if fence == b"---" { b"---" } else { b"+++" });
+                        continue;
+                    }
+                    self.state = FrontMatterState::Done;
+                    let mut rest = std::mem::take(&mut self.held);
+                    rest.extend_from_slice(&input[start..]);
+                    return rest;
+                }
+                FrontMatterState::Inside(closing) if fence == closing => {
+                    self.state = FrontMatterState::Done;
+                    self.held.clear();
+                    return input[start..].to_vec();
+                }
+                _ => (),
+            }
+        }
+        self.held.extend_from_slice(&input[start..]);
+        vec![]
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, FrontMatterState::Done)
+    }
+
+    // Input held back at the end, which was not front matter after all
+    pub fn finish(self) -> Vec<u8> {
+        self.held
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(text: &str) -> Vec<String> {
+        filter_as(Format::Markdown, text)
+    }
+
+    fn filter_as(format: Format, text: &str) -> Vec<String> {
+        let mut filter = Filter::new(format);
+        text.lines()
+            .map(|line| filter.line(line).unwrap_or_default())
+            .collect()
+    }
+
+    #[test]
+    fn markdown() {
+        let text = "---\n\
+                    title: Post\n\
+                    ---\n\
+                    # A *first* heading #\n\
+                    See [the docs](https://example.com/docs \"Docs\") and ![a logo](logo.png).\n\
+                    > - [x] **done** with `snake_case`\n\
+                    ```rust\n\
+                    fn main() {}\n\
+                    ```\n\
+                    ***\n\
+                    | a | b |\n\
+                    Visit <https://example.com> or [this][ref].\n\
+                    [ref]: https://example.com\n\
+                    12. _last_ item";
+        assert_eq!(
+            filter(text),
+            [
+                "",
+                "",
+                "",
+                "A first heading ",
+                "See the docs and a logo.",
+                "done with snake_case",
+                "",
+                "",
+                "",
+                "",
+                "  a   b  ",
+                "Visit  or this.",
+                "",
+                "last item"
+            ]
+        );
+    }
+
+    #[test]
+    fn html() {
+        let text = "<!DOCTYPE html><html><head><style>p { color: red; }\n\
+                    </style><script type=\"text/javascript\">if (a < b) {}</script></head>\n\
+                    <body><h1 class=\"title\">Fish &amp; Chips</h1><p>1 &lt; 2 &#8212; <b>bold</b>ed\n\
+                    <a href=\"/x?a=1&b=2\" title='a > b'>link</a><!-- a\n\
+                    comment --></p><p>second</p> &copy;&nbsp;2024 &unknown; A & B</body>";
+        assert_eq!(
+            filter_as(Format::Html, text),
+            [
+                "",
+                "",
+                " Fish & Chips  1 < 2 \u{2014} bolded",
+                "link",
+                "  second  \u{a9}\u{a0}2024 &unknown; A & B"
+            ]
+        );
+    }
+
+    #[test]
+    fn latex() {
+        let text = "\\documentclass[a4paper]{article}\n\
+                    \\usepackage{amsmath}\n\
+                    \\begin{document}\n\
+                    \\section{Intro}\\label{sec:intro}\n\
+                    Energy is $E = mc^2$, see~\\cite[p.~3]{einstein}. % not counted\n\
+                    \\begin{align*}\n\
+                    a &= b \\\\\n\
+                    \\end{align*}\n\
+                    It costs 5\\% \\emph{more} \\(x\\) than \\href{https://x.org}{the site}.\\\\\n\
+                    \\end{document}";
+        assert_eq!(
+            filter_as(Format::Latex, text),
+            [
+                "",
+                "",
+                "",
+                "Intro",
+                "Energy is , see . ",
+                "",
+                "",
+                "",
+                "It costs 5% more  than the site. ",
+                ""
+            ]
+        );
+    }
+
+    #[test]
+    fn subtitles() {
+        let srt = "1\n\
+                   00:00:01,000 --> 00:00:04,000\n\
+                   <i>Hello</i> there,\n\
+                   {\\an8}General Kenobi!\n\
+                   \n\
+                   2\n\
+                   00:00:05,000 --> 00:00:06,000\n\
+                   Tom &amp; Jerry\n";
+        assert_eq!(
+            filter_as(Format::Subtitles, srt),
+            [
+                "",
+                "",
+                "Hello there,",
+                "General Kenobi!",
+                "",
+                "",
+                "",
+                "Tom & Jerry"
+            ]
+        );
+        let vtt = "WEBVTT - Title\n\
+                   \n\
+                   NOTE a comment\n\
+                   that spans lines\n\
+                   \n\
+                   intro\n\
+                   00:01.000 --> 00:04.000 position:10%\n\
+                   <v Roger>It's {not} a tag\n";
+        let mut filter = Filter::new(Format::Subtitles);
+        let text: Vec<String> = vtt
+            .lines()
+            .map(|line| filter.line(line).unwrap_or_default())
+            .collect();
+        assert_eq!(text, ["", "", "", "", "", "", "", "It's {not} a tag"]);
+        assert_eq!(filter.cues(), 1);
+        assert_eq!(Format::for_file("movie.de.SRT"), Some(Format::Subtitles));
+        assert_eq!(Format::for_file("notes.txt"), None);
+    }
+
+    #[test]
+    fn mbox() {
+        let mbox = "From alice@example.com Mon Jan  1 00:00:00 2024\n\
+                    From: Alice <alice@example.com>\n\
+                    Subject: Hello\n\
+                    \n\
+                    Hi Bob,\n\
+                    >From now on, we meet on Mondays.\n\
+                    \n\
+                    From bob@example.com Tue Jan  2 00:00:00 2024\n\
+                    Subject: Re: Hello\n\
+                    \n\
+                    Fine.\n";
+        let mut filter = Filter::new(Format::Mbox);
+        let text: Vec<Option<String>> = mbox.lines().map(|line| filter.line(line)).collect();
+        assert_eq!(
+            text,
+            [
+                None,
+                None,
+                None,
+                None,
+                Some("Hi Bob,".to_owned()),
+                Some("From now on, we meet on Mondays.".to_owned()),
+                Some("".to_owned()),
+                None,
+                None,
+                None,
+                Some("Fine.".to_owned())
+            ]
+        );
+        assert_eq!(filter.messages(), 2);
+    }
+
+    #[test]
+    fn front_matter() {
+        let mut front_matter = FrontMatter::default();
+        assert_eq!(front_matter.filter(b"---\ntitle: "), b"");
+        assert_eq!(front_matter.filter(b"Post\n---\r\nBody\n"), b"Body\n");
+        assert!(front_matter.is_done());
+        assert_eq!(front_matter.filter(b"---\n"), b"---\n");
+
+        let mut front_matter = FrontMatter::default();
+        assert_eq!(
+            front_matter.filter(b"Text\n---\nmore\n---\n"),
+            b"Text\n---\nmore\n---\n"
+        );
+
+        let mut front_matter = FrontMatter::default();
+        assert_eq!(front_matter.filter(b"+++\nnot closed\n"), b"");
+        assert_eq!(front_matter.finish(), b"+++\nnot closed\n");
+    }
+}
@@ -0,0 +1,55 @@
+use std::fs::File;
+use std::io::{self, Error};
+
+// How much splice is asked to move at a time
+#[cfg(target_os = "linux")]
+const CHUNK_SIZE: usize = 1 << 20;
+
+//
+// Read a pipe to its end and return how many bytes came through it. On Linux, splice
+// moves them to /dev/null within the kernel, instead of copying them to wc first.
+// Returns None if the file is not a pipe or cannot be spliced, without reading from it.
+// Only named pipes and those given with --fd get here: wc does not read standard input yet.
+//
+#[cfg(target_os = "linux")]
+pub fn discard(f: &File) -> Result<Option<u64>, Error> {
+    use std::os::fd::AsRawFd;
+    use std::os::unix::fs::FileTypeExt;
+
+    if !f.metadata()?.file_type().is_fifo() {
+        return Ok(None);
+    }
+    let null = std::fs::OpenOptions::new().write(true).open("/dev/null")?;
+    let mut total = 0;
+    loop {
+        // SAFETY: both descriptors stay open during the call, and pipes have no offsets
+        let n = unsafe {
+            libc::splice(
+                f.as_raw_fd(),
+                std::ptr::null_mut(),
+                null.as_raw_fd(),
+                std::ptr::null_mut(),
+                CHUNK_SIZE,
+                libc::SPLICE_F_MOVE,
+            )
+        };
+        if n < 0 {
+            let err = Error::last_os_error();
+            match err.kind() {
+                io::ErrorKind::Interrupted => continue,
+                io::ErrorKind::InvalidInput if total == 0 => return Ok(None),
+                _ => return Err(err),
+            }
+        }
+        if n == 0 || crate::signals::interrupted() {
+            return Ok(Some(total));
+        }
+        total += n as u64;
+        crate::signals::read(n as usize);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn discard(_f: &File) -> Result<Option<u64>, Error> {
+    Ok(None)
+}
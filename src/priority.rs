@@ -0,0 +1,59 @@
+//
+// Get out of the way of interactive work for --nice: run with the lowest CPU priority,
+// read only when the disk is otherwise idle and do not read ahead of what is counted.
+// All of it is best effort, wc counts the same if the system does not allow it.
+//
+use std::fs::File;
+
+#[cfg(unix)]
+pub fn lower() {
+    // SAFETY: only changes the priority of this process
+    if unsafe { libc::nice(19) } == -1 {
+        log::debug!("nice: {}", std::io::Error::last_os_error());
+    }
+    #[cfg(target_os = "linux")]
+    {
+        const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+        const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+        const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+        // SAFETY: ioprio_set takes three integers, 0 is this process
+        let result = unsafe {
+            libc::syscall(
+                libc::SYS_ioprio_set,
+                IOPRIO_WHO_PROCESS,
+                0,
+                IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+            )
+        };
+        if result == -1 {
+            log::debug!("ioprio_set: {}", std::io::Error::last_os_error());
+        }
+    }
+}
+
+// Background mode lowers both the CPU and the I/O priority
+#[cfg(windows)]
+pub fn lower() {
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentProcess, SetPriorityClass, PROCESS_MODE_BACKGROUND_BEGIN,
+    };
+
+    // SAFETY: only changes the priority of this process
+    if unsafe { SetPriorityClass(GetCurrentProcess(), PROCESS_MODE_BACKGROUND_BEGIN) } == 0 {
+        log::debug!("SetPriorityClass: {}", std::io::Error::last_os_error());
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn lower() {}
+
+#[cfg(target_os = "linux")]
+pub fn no_readahead(file: &File) {
+    use std::os::unix::io::AsRawFd;
+
+    // SAFETY: the descriptor belongs to the open file
+    unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_RANDOM) };
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn no_readahead(_file: &File) {}
@@ -0,0 +1,294 @@
+//
+// Selects part of the raw input before it is counted, so that all counts, bytes included,
+// are of that part only. Lines end at line feed bytes, which is exact for UTF-8 and other
+// ASCII-compatible encodings.
+//
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{Error, SeekFrom};
+use wc::cli::Range;
+
+// How much of a file is read at a time while looking back for the start of its last lines
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+#[derive(Clone, Debug)]
+pub struct ByteRange {
+    start: u64,
+    end: Option<u64>,
+    // Offset of the next input
+    offset: u64,
+}
+
+impl ByteRange {
+    pub fn new(range: Range) -> ByteRange {
+        ByteRange {
+            start: range.start.unwrap_or(0),
+            end: range.end,
+            offset: 0,
+        }
+    }
+
+    // The part of the input within the range
+    pub fn select<'a>(&mut self, input: &'a [u8]) -> &'a [u8] {
+        let offset = self.offset;
+        self.offset += input.len() as u64;
+        let from = self.start.saturating_sub(offset).min(input.len() as u64);
+        let to = self.end.map_or(input.len() as u64, |end| {
+            end.saturating_sub(offset).min(input.len() as u64)
+        });
+        &input[from as usize..to.max(from) as usize]
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.end.is_some_and(|end| self.offset >= end)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LineRange {
+    first: u64,
+    last: Option<u64>,
+    // Number of the line the next input belongs to
+    line: u64,
+}
+
+impl LineRange {
+    pub fn new(range: Range) -> LineRange {
+        LineRange {
+            first: range.start.unwrap_or(1),
+            last: range.end,
+            line: 1,
+        }
+    }
+
+    // The part of the input on the selected lines
+    pub fn select(&mut self, mut input: &[u8]) -> Vec<u8> {
+        let mut selected = vec![];
+        while !input.is_empty() && !self.is_done() {
+            let end = match input.iter().position(|b| *b == b'\n') {
+                Some(position) => position + 1,
+                None => input.len(),
+            };
+            if self.line >= self.first {
+                selected.extend_from_slice(&input[..end]);
+            }
+            if input[end - 1] == b'\n' {
+                self.line += 1;
+            }
+            input = &input[end..];
+        }
+        selected
+    }
+
+    // Whether the last selected line is over, so that the rest need not be read
+    pub fn is_done(&self) -> bool {
+        self.last.is_some_and(|last| self.line > last)
+    }
+}
+
+//
+// Keeps only the lines matching one pattern and leaves out those matching another.
+// A line is held back until its line break, and matched without it.
+//
+#[derive(Clone, Debug)]
+pub struct LineFilter {
+    matching: Option<regex::Regex>,
+    ignoring: Option<regex::Regex>,
+    line: Vec<u8>,
+}
+
+impl LineFilter {
+    pub fn new(matching: Option<regex::Regex>, ignoring: Option<regex::Regex>) -> LineFilter {
+        LineFilter {
+            matching,
+            ignoring,
+            line: vec![],
+        }
+    }
+
+    fn is_selected(&self) -> bool {
+        let text = String::from_utf8_lossy(&self.line);
+        let text = text.trim_end_matches(['\n', '\r']);
+        self.matching
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(text))
+            && !self
+                .ignoring
+                .as_ref()
+                .is_some_and(|pattern| pattern.is_match(text))
+    }
+
+    // The complete lines of the input that are not left out
+    pub fn select(&mut self, mut input: &[u8]) -> Vec<u8> {
+        let mut selected = vec![];
+        while let Some(position) = input.iter().position(|b| *b == b'\n') {
+            self.line.extend_from_slice(&input[..position + 1]);
+            if self.is_selected() {
+                selected.extend_from_slice(&self.line);
+            }
+            self.line.clear();
+            input = &input[position + 1..];
+        }
+        self.line.extend_from_slice(input);
+        selected
+    }
+
+    // The last line, if it has no line break and is not left out
+    pub fn finish(self) -> Vec<u8> {
+        if self.is_selected() {
+            self.line
+        } else {
+            vec![]
+        }
+    }
+}
+
+//
+// Holds back the last lines of the input, which are counted when it is over.
+// A last line without a line break is one of them, as with tail.
+//
+#[derive(Clone, Debug)]
+pub struct Tail {
+    count: u64,
+    lines: VecDeque<Vec<u8>>,
+}
+
+impl Tail {
+    pub fn new(count: u64) -> Tail {
+        Tail {
+            count,
+            lines: VecDeque::new(),
+        }
+    }
+
+    pub fn select(&mut self, mut input: &[u8]) {
+        while !input.is_empty() {
+            let end = match input.iter().position(|b| *b == b'\n') {
+                Some(position) => position + 1,
+                None => input.len(),
+            };
+            match self.lines.back_mut() {
+                Some(line) if line.last() != Some(&b'\n') => line.extend_from_slice(&input[..end]),
+                _ => self.lines.push_back(input[..end].to_vec()),
+            }
+            if self.lines.len() as u64 > self.count {
+                self.lines.pop_front();
+            }
+            input = &input[end..];
+        }
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.lines.into_iter().flatten().collect()
+    }
+}
+
+// Offset of the first of the last count lines of a file
+pub fn tail_start(f: &mut File, count: u64) -> Result<u64, Error> {
+    let len = f.metadata()?.len();
+    if count == 0 {
+        return Ok(len);
+    }
+    let mut end = len;
+    let mut line_breaks = 0;
+    let mut chunk = vec![];
+    while end > 0 {
+        let start = end.saturating_sub(CHUNK_SIZE);
+        f.seek(SeekFrom::Start(start))?;
+        chunk.clear();
+        (&mut *f).take(end - start).read_to_end(&mut chunk)?;
+        for (position, _) in chunk.iter().enumerate().rev().filter(|(_, b)| **b == b'\n') {
+            let offset = start + position as u64;
+            // The line break ending the file does not start another line
+            if offset + 1 == len {
+                continue;
+            }
+            line_breaks += 1;
+            if line_breaks == count {
+                return Ok(offset + 1);
+            }
+        }
+        end = start;
+    }
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testfile::TempPath;
+
+    #[test]
+    fn bytes() {
+        let mut range = ByteRange::new(Range {
+            start: Some(2),
+            end: Some(7),
+        });
+        assert_eq!(range.select(b"a"), b"");
+        assert_eq!(range.select(b"bcdef"), b"cdef");
+        assert!(!range.is_done());
+        assert_eq!(range.select(b"ghi"), b"g");
+        assert!(range.is_done());
+        assert_eq!(range.select(b"jk"), b"");
+    }
+
+    #[test]
+    fn lines() {
+        let mut range = LineRange::new(Range {
+            start: Some(2),
+            end: Some(3),
+        });
+        let mut selected = range.select(b"one\ntw");
+        selected.extend(range.select(b"o\nthree\nfo"));
+        assert!(range.is_done());
+        selected.extend(range.select(b"ur\n"));
+        assert_eq!(selected, b"two\nthree\n");
+
+        let mut range = LineRange::new(Range {
+            start: None,
+            end: None,
+        });
+        assert_eq!(range.select(b"a\nb"), b"a\nb");
+        assert!(!range.is_done());
+    }
+
+    #[test]
+    fn ignored_lines() {
+        let mut filter = LineFilter::new(None, Some(regex::Regex::new("#").unwrap()));
+        let mut selected = filter.select(b"a = 1\n# comm");
+        selected.extend(filter.select(b"ent\r\nb = 2\n#"));
+        selected.extend(filter.finish());
+        assert_eq!(selected, b"a = 1\nb = 2\n");
+    }
+
+    #[test]
+    fn matching_lines() {
+        let mut filter = LineFilter::new(
+            Some(regex::Regex::new("ERROR").unwrap()),
+            Some(regex::Regex::new("retry").unwrap()),
+        );
+        let mut selected = filter.select(b"INFO up\nERROR disk\nERROR net, retry\nERR");
+        selected.extend(filter.select(b"OR end"));
+        selected.extend(filter.finish());
+        assert_eq!(selected, b"ERROR disk\nERROR end");
+    }
+
+    #[test]
+    fn tail() {
+        let text = b"one\ntwo\nthree\nfour";
+        for (count, expected) in [(0, &b""[..]), (2, b"three\nfour"), (9, text)] {
+            let mut tail = Tail::new(count);
+            tail.select(&text[..6]);
+            tail.select(&text[6..]);
+            assert_eq!(tail.finish(), expected);
+        }
+
+        let path = TempPath::file("tail", "one\ntwo\nthree\n");
+        let mut f = File::open(&path).unwrap();
+        let starts: Vec<u64> = (0..5)
+            .map(|count| tail_start(&mut f, count).unwrap())
+            .collect();
+        assert_eq!(starts, [14, 8, 4, 0, 0]);
+    }
+}
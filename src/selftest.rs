@@ -0,0 +1,76 @@
+use std::fs;
+use std::io::{self, Error};
+use std::path::Path;
+use std::process::{Command, Output};
+
+// Files that tend to trip up a wc, written to the corpus directory
+fn corpus() -> Vec<(&'static str, Vec<u8>)> {
+    vec![
+        (
+            "multibyte.txt",
+            "Grüße aus Köln\n世界 你好\nnaïve café 👍🏽\n".into(),
+        ),
+        (
+            "long-line.txt",
+            format!("{}\n", "lorem ipsum ".repeat(20_000)).into(),
+        ),
+        ("no-newline.txt", "the last line\nhas no newline".into()),
+        ("blank-lines.txt", "\n\n  \t\n\n".into()),
+        ("empty.txt", vec![]),
+        ("binary.bin", (0..=255).cycle().take(4096).collect()),
+    ]
+}
+
+const FLAGS: &[&[&str]] = &[&[], &["-c"], &["-m"], &["-l"], &["-w"], &["-L"]];
+
+fn run(program: &Path, args: &[&str], dir: &Path) -> Result<Output, Error> {
+    Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .env("LC_ALL", "C.UTF-8")
+        .output()
+}
+
+//
+// Run this wc and the one of the system with the same options on the corpus, one file at a
+// time and all together, and print every run where their output or exit status differ.
+// The corpus is kept in dir if one is given.
+//
+pub fn self_test(out: &mut dyn io::Write, dir: Option<&str>) -> Result<(), Error> {
+    let temporary = std::env::temp_dir().join(format!("wc-self-test-{}", std::process::id()));
+    let dir = dir.map(Path::new).unwrap_or(&temporary);
+    fs::create_dir_all(dir)?;
+    let corpus = corpus();
+    for (name, content) in &corpus {
+        fs::write(dir.join(name), content)?;
+    }
+    let this = std::env::current_exe()?;
+    let system = Path::new("wc");
+    let all: Vec<&str> = corpus.iter().map(|(name, _)| *name).collect();
+    let mut runs = 0;
+    let mut differences = 0;
+    for flags in FLAGS {
+        for files in all.chunks(1).chain([all.as_slice()]) {
+            let args: Vec<&str> = flags.iter().chain(files).copied().collect();
+            let (expected, actual) = (run(system, &args, dir)?, run(&this, &args, dir)?);
+            runs += 1;
+            if expected.stdout == actual.stdout && expected.status.code() == actual.status.code() {
+                continue;
+            }
+            differences += 1;
+            writeln!(out, "DIFFERS: wc {}", args.join(" "))?;
+            for (label, output) in [("system", &expected), ("this", &actual)] {
+                writeln!(out, "--- {} wc, {}", label, output.status)?;
+                out.write_all(&output.stdout)?;
+            }
+        }
+    }
+    if dir == temporary {
+        fs::remove_dir_all(dir)?;
+    }
+    writeln!(out, "{} of {} runs differ", differences, runs)?;
+    if differences > 0 {
+        return Err(Error::other("output differs from the system wc"));
+    }
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use std::cell::OnceCell;
+use std::io::Error;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    // Set once nobody waits for the count on this thread anymore, e.g. after --timeout
+    static ABANDONED: OnceCell<Arc<AtomicBool>> = const { OnceCell::new() };
+}
+
+// Progress of the count so far, reported when asked for with a signal
+static PROGRESS_REQUESTED: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+static FILES_DONE: AtomicUsize = AtomicUsize::new(0);
+static BYTES_READ: AtomicU64 = AtomicU64::new(0);
+static CURRENT_FILE: Mutex<String> = Mutex::new(String::new());
+
+//
+// Make Ctrl-C stop counting instead of killing wc, so that what was counted
+// so far can still be printed. A second Ctrl-C exits right away.
+//
+pub fn catch_interrupt() -> Result<(), Error> {
+    ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+    })
+    .map_err(Error::other)
+}
+
+// Whether to stop reading, because of Ctrl-C or because this thread's count was abandoned
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+        || ABANDONED.with(|abandoned| {
+            abandoned
+                .get()
+                .is_some_and(|abandoned| abandoned.load(Ordering::Relaxed))
+        })
+}
+
+// Stop reading on this thread, at the next read, once the flag is set
+pub fn abandon_on(flag: Arc<AtomicBool>) {
+    ABANDONED.with(|abandoned| {
+        let _ = abandoned.set(flag);
+    });
+}
+
+//
+// Like dd, report the progress on stderr on SIGUSR1, or SIGINFO (Ctrl-T) on BSD and macOS.
+// The handler only sets a flag, the report is printed by the next read.
+//
+#[cfg(unix)]
+pub fn catch_progress_request() -> Result<(), Error> {
+    let requested = PROGRESS_REQUESTED.get_or_init(Arc::default);
+    signal_hook::flag::register(signal_hook::consts::SIGUSR1, Arc::clone(requested))?;
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly"
+    ))]
+    signal_hook::flag::register(signal_hook::consts::SIGINFO, Arc::clone(requested))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn catch_progress_request() -> Result<(), Error> {
+    Ok(())
+}
+
+pub fn start_file(filename: &str) {
+    filename.clone_into(&mut CURRENT_FILE.lock().unwrap());
+}
+
+pub fn file_done() {
+    FILES_DONE.fetch_add(1, Ordering::Relaxed);
+}
+
+// Account for bytes read and report the progress if that was asked for
+pub fn read(bytes: usize) {
+    let bytes_read = BYTES_READ.fetch_add(bytes as u64, Ordering::Relaxed) + bytes as u64;
+    let requested = PROGRESS_REQUESTED
+        .get()
+        .is_some_and(|requested| requested.swap(false, Ordering::Relaxed));
+    if requested {
+        let file = CURRENT_FILE.lock().unwrap().clone();
+        let message = crate::i18n::message(
+            "progress",
+            &[
+                ("files", FILES_DONE.load(Ordering::Relaxed).into()),
+                ("bytes", bytes_read.into()),
+                ("file", file.into()),
+            ],
+        );
+        eprintln!("wc: {}", message);
+    }
+}
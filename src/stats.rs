@@ -0,0 +1,49 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+// Files left out by --max-filesize, --devices or as hidden files
+static SKIPPED: AtomicUsize = AtomicUsize::new(0);
+
+pub fn skipped() {
+    SKIPPED.fetch_add(1, Ordering::Relaxed);
+}
+
+//
+// What --stats prints on stderr after counting: how many files were counted,
+// skipped or could not be read, and how fast.
+//
+pub struct Stats {
+    started: Instant,
+    pub files: usize,
+    pub errors: usize,
+}
+
+impl Stats {
+    pub fn start() -> Stats {
+        Stats {
+            started: Instant::now(),
+            files: 0,
+            errors: 0,
+        }
+    }
+
+    pub fn report(&self, bytes: usize) -> String {
+        let seconds = self.started.elapsed().as_secs_f64();
+        let throughput = if seconds > 0.0 {
+            bytes as f64 / seconds / 1_000_000.0
+        } else {
+            0.0
+        };
+        crate::i18n::message(
+            "stats",
+            &[
+                ("files", self.files.into()),
+                ("skipped", SKIPPED.load(Ordering::Relaxed).into()),
+                ("errors", self.errors.into()),
+                ("bytes", bytes.into()),
+                ("seconds", format!("{:.3}", seconds).into()),
+                ("throughput", format!("{:.1}", throughput).into()),
+            ],
+        )
+    }
+}
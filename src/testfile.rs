@@ -0,0 +1,73 @@
+//
+// Files, directories and named pipes for tests. Each gets a path of its own in the temporary
+// directory, so that tests running at the same time don't share them, and is removed when
+// dropped, also if the test fails.
+//
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug)]
+pub struct TempPath(PathBuf);
+
+impl TempPath {
+    // Nothing is created yet. The name ends the path, so that it may have an extension.
+    pub fn new(name: &str) -> TempPath {
+        let unique = NEXT.fetch_add(1, Ordering::Relaxed);
+        let name = format!("wc-{}-{}-{}", std::process::id(), unique, name);
+        TempPath(std::env::temp_dir().join(name))
+    }
+
+    pub fn file(name: &str, contents: impl AsRef<[u8]>) -> TempPath {
+        let path = TempPath::new(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    pub fn dir(name: &str) -> TempPath {
+        let path = TempPath::new(name);
+        std::fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    pub fn fifo(name: &str) -> TempPath {
+        let path = TempPath::new(name);
+        assert!(std::process::Command::new("mkfifo")
+            .arg(&*path)
+            .status()
+            .unwrap()
+            .success());
+        path
+    }
+
+    pub fn name(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+}
+
+impl Deref for TempPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempPath {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = if self.0.is_dir() {
+            std::fs::remove_dir_all(&self.0)
+        } else {
+            std::fs::remove_file(&self.0)
+        };
+    }
+}
@@ -0,0 +1,234 @@
+use crate::{Column, Metrics};
+use std::cmp::Ordering;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+
+//
+// A budget like `lines>500` that must not be exceeded, checked either against
+// every file or, with a `total.` prefix, against the total of all files.
+//
+pub struct Condition {
+    expression: String,
+    total: bool,
+    column: Column,
+    fails_on: &'static [Ordering],
+    limit: usize,
+}
+
+const OPERATORS: &[(&str, &[Ordering])] = &[
+    (">=", &[Ordering::Greater, Ordering::Equal]),
+    ("<=", &[Ordering::Less, Ordering::Equal]),
+    ("!=", &[Ordering::Less, Ordering::Greater]),
+    ("==", &[Ordering::Equal]),
+    (">", &[Ordering::Greater]),
+    ("<", &[Ordering::Less]),
+    ("=", &[Ordering::Equal]),
+];
+
+impl Condition {
+    pub fn parse(expression: &str) -> Result<Condition, Error> {
+        let invalid = || {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid condition '{}'", expression),
+            )
+        };
+        let (position, operator, fails_on) = OPERATORS
+            .iter()
+            .filter_map(|(operator, fails_on)| {
+                expression
+                    .find(operator)
+                    .map(|position| (position, *operator, *fails_on))
+            })
+            .min_by_key(|(position, _, _)| *position)
+            .ok_or_else(invalid)?;
+        let name = expression[..position].trim();
+        let (total, name) = match name.strip_prefix("total.") {
+            Some(name) => (true, name),
+            None => (false, name),
+        };
+        let column = Column::parse(name)?;
+        let limit = expression[position + operator.len()..].trim();
+        let limit = if column == Column::Bytes {
+            crate::parse_size(limit)? as usize
+        } else {
+            limit.parse().map_err(|_| invalid())?
+        };
+        Ok(Condition {
+            expression: expression.to_owned(),
+            total,
+            column,
+            fails_on,
+            limit,
+        })
+    }
+
+    fn is_violated_by(&self, m: &Metrics) -> bool {
+        self.fails_on
+            .contains(&self.column.value(m).cmp(&self.limit))
+    }
+
+    pub fn column(&self) -> Column {
+        self.column
+    }
+
+    // Whether a file, or with is_total the total, meets a condition like those of --highlight
+    pub fn is_met_by(&self, m: &Metrics, is_total: bool) -> bool {
+        self.total == is_total && self.is_violated_by(m)
+    }
+
+    // Every file, or the total, that violates the condition
+    pub fn violations<'a>(
+        &self,
+        all_metrics: &'a [Metrics],
+        total: &'a Metrics,
+    ) -> Vec<&'a Metrics> {
+        if self.total {
+            Some(total)
+                .filter(|m| self.is_violated_by(m))
+                .into_iter()
+                .collect()
+        } else {
+            all_metrics
+                .iter()
+                .filter(|m| self.is_violated_by(m))
+                .collect()
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+// Marks the error returned when a --fail-if condition is violated,
+// so that it can be told apart from other failures by its exit status.
+#[derive(Debug)]
+pub struct Violated;
+
+impl fmt::Display for Violated {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("--fail-if condition violated")
+    }
+}
+
+impl std::error::Error for Violated {}
+
+//
+// Report every violated condition on stderr.
+// Fails with `Violated` if there was at least one.
+//
+pub fn check(conditions: &[Condition], all_metrics: &[Metrics]) -> Result<(), Error> {
+    let (total, _) = crate::calculate_total_and_max_width_per_column(all_metrics);
+    let mut violated = false;
+    for condition in conditions {
+        for m in condition.violations(all_metrics, &total) {
+            eprintln!(
+                "wc: {}: {} ({})",
+                m.filename,
+                condition,
+                condition.column.value(m)
+            );
+            violated = true;
+        }
+    }
+    if violated {
+        Err(Error::other(Violated))
+    } else {
+        Ok(())
+    }
+}
+
+//
+// List every file that is empty or has no complete line on stderr.
+// Fails with `Violated` if there was at least one.
+//
+pub fn check_empty(all_metrics: &[Metrics]) -> Result<(), Error> {
+    let mut violated = false;
+    for m in all_metrics {
+        if m.bytes == 0 {
+            eprintln!("wc: {}: {}", m.filename, crate::i18n::text("empty"));
+        } else if m.lines == 0 {
+            eprintln!("wc: {}: {}", m.filename, crate::i18n::text("no-lines"));
+        } else {
+            continue;
+        }
+        violated = true;
+    }
+    if violated {
+        Err(Error::other(Violated))
+    } else {
+        Ok(())
+    }
+}
+
+//
+// List every file whose records do not all have the same number of fields on stderr.
+// Fails with `Violated` if there was at least one.
+//
+pub fn check_fields(all_metrics: &[Metrics]) -> Result<(), Error> {
+    let mut violated = false;
+    for m in all_metrics.iter().filter(|m| m.min_fields != m.fields) {
+        let message = crate::i18n::message(
+            "inconsistent-fields",
+            &[
+                ("min", m.min_fields.into()),
+                ("max", m.fields.into()),
+                ("modal", m.modal_fields.into()),
+            ],
+        );
+        eprintln!("wc: {}: {}", m.filename, message);
+        violated = true;
+    }
+    if violated {
+        Err(Error::other(Violated))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(filename: &str, lines: usize, bytes: usize) -> Metrics {
+        Metrics {
+            lines,
+            bytes,
+            filename: filename.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn conditions() {
+        let all = [metrics("a", 10, 2048), metrics("b", 600, 100)];
+        let (total, _) = crate::calculate_total_and_max_width_per_column(&all);
+        let files = |expression: &str| -> Vec<String> {
+            Condition::parse(expression)
+                .unwrap()
+                .violations(&all, &total)
+                .iter()
+                .map(|m| m.filename.clone())
+                .collect()
+        };
+        assert_eq!(files("lines>500"), ["b"]);
+        assert_eq!(files("lines >= 10"), ["a", "b"]);
+        assert_eq!(files("lines<=10"), ["a"]);
+        assert_eq!(files("bytes>1K"), ["a"]);
+        assert_eq!(files("total.lines>600"), ["total"]);
+        assert_eq!(files("total.lines>1000"), Vec::<String>::new());
+        assert!(Condition::parse("lines").is_err());
+        assert!(Condition::parse("pages>1").is_err());
+        assert!(Condition::parse("lines>many").is_err());
+    }
+
+    #[test]
+    fn empty_files() {
+        assert!(check_empty(&[metrics("a", 1, 2)]).is_ok());
+        assert!(check_empty(&[metrics("a", 1, 2), metrics("b", 0, 0)]).is_err());
+        assert!(check_empty(&[metrics("a", 0, 5)]).is_err());
+    }
+}
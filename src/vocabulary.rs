@@ -0,0 +1,127 @@
+use crate::Metrics;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufWriter, Error};
+
+#[derive(Serialize)]
+struct Entry<'a> {
+    word: &'a str,
+    count: usize,
+}
+
+// The vocabulary of all files together, most frequent words first
+fn combine(all_metrics: &[Metrics]) -> Vec<(&str, usize)> {
+    let mut combined: HashMap<&str, usize> = HashMap::new();
+    for m in all_metrics {
+        for (word, count) in &m.vocabulary {
+            *combined.entry(word).or_default() += count;
+        }
+    }
+    let mut words: Vec<(&str, usize)> = combined.into_iter().collect();
+    words.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    words
+}
+
+// What a table needs around each word and its count, roughly
+const ENTRY_OVERHEAD: usize = 64;
+
+// Roughly the memory a word takes in a vocabulary, for --max-memory
+pub fn entry_size(word: &str) -> usize {
+    word.len() + ENTRY_OVERHEAD
+}
+
+//
+// Leave out the rarest words until the vocabulary takes at most half the budget,
+// so that it does not need to be pruned again right away. Counts of words that come
+// back are too low then. Returns the size of what is left.
+//
+pub fn prune(vocabulary: &mut HashMap<String, usize>, budget: usize) -> usize {
+    let mut size: usize = vocabulary.keys().map(|word| entry_size(word)).sum();
+    while size > budget / 2 {
+        let rarest = match vocabulary.values().min() {
+            Some(rarest) => *rarest,
+            None => break,
+        };
+        vocabulary.retain(|word, count| {
+            if *count == rarest {
+                size -= entry_size(word);
+            }
+            *count > rarest
+        });
+    }
+    size
+}
+
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+//
+// Write every word with the number of times it occurs in all files.
+// The file is JSON if its name ends in .json, CSV otherwise.
+//
+pub fn export(path: &str, all_metrics: &[Metrics]) -> Result<(), Error> {
+    let words = combine(all_metrics);
+    let mut writer = BufWriter::new(File::create(path)?);
+    if path.ends_with(".json") {
+        let entries: Vec<Entry> = words
+            .into_iter()
+            .map(|(word, count)| Entry { word, count })
+            .collect();
+        serde_json::to_writer_pretty(&mut writer, &entries)?;
+    } else {
+        writeln!(writer, "word,count")?;
+        for (word, count) in words {
+            writeln!(writer, "{},{}", csv_field(word), count)?;
+        }
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{count_reader, CountOptions};
+
+    #[test]
+    fn combined_vocabulary() {
+        let opts = CountOptions {
+            vocabulary: true,
+            ..Default::default()
+        };
+        let a = count_reader(&mut &b"the cat and the hat\n"[..], "a", &opts).unwrap();
+        let b = count_reader(&mut &b"a \"quoted\" cat,"[..], "b", &opts).unwrap();
+        assert_eq!(
+            combine(&[a, b]),
+            [
+                ("the", 2),
+                ("\"quoted\"", 1),
+                ("a", 1),
+                ("and", 1),
+                ("cat", 1),
+                ("cat,", 1),
+                ("hat", 1)
+            ]
+        );
+        assert_eq!(csv_field("cat,"), "\"cat,\"");
+        assert_eq!(csv_field("\"quoted\""), "\"\"\"quoted\"\"\"");
+    }
+
+    #[test]
+    fn pruned_vocabulary() {
+        let opts = CountOptions {
+            vocabulary: true,
+            max_memory: Some(2 * entry_size("the")),
+            ..Default::default()
+        };
+        let m = count_reader(&mut &b"the cat the hat the"[..], "a", &opts).unwrap();
+        assert_eq!(m.vocabulary.len(), 1);
+        assert_eq!(m.vocabulary["the"], 3);
+    }
+}
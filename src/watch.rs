@@ -0,0 +1,302 @@
+use crate::{Cli, CountOptions, Counter, Metrics, ShowOptions};
+use notify::{RecursiveMode, Watcher};
+use std::collections::VecDeque;
+use std::fs::{File, Metadata};
+use std::io::prelude::*;
+use std::io::{self, Error, ErrorKind, SeekFrom};
+use std::path::Path;
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Editors usually write a file in several steps (truncate, write, rename, ...).
+// Events arriving within this period are collapsed into one recount.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+fn to_io_error(err: notify::Error) -> Error {
+    Error::other(err)
+}
+
+fn required_files(cli: &Cli, flag: &str) -> Result<Vec<String>, Error> {
+    match crate::input_files(cli)? {
+        Some(files) => Ok(files),
+        None => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} requires at least one file", flag),
+        )),
+    }
+}
+
+//
+// Re-run the count whenever one of the given files changes and reprint the table.
+// Runs until the process is interrupted.
+//
+pub fn watch(cli: &Cli) -> Result<(), Error> {
+    let files = required_files(cli, "--watch")?;
+
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(to_io_error)?;
+    let mut printed = vec![];
+    loop {
+        // Watch again on every round: editors that save by renaming a new file
+        // over the old one would otherwise leave us watching a deleted inode.
+        for file in &files {
+            match watcher.watch(Path::new(file), RecursiveMode::NonRecursive) {
+                Err(err) if !cli.quiet => crate::errors::report_error(file, &to_io_error(err)),
+                _ => (),
+            }
+        }
+        if cli.clear || cli.live {
+            print!("\x1b[2J\x1b[H");
+        }
+        match crate::print_count_since(&mut io::stdout().lock(), cli, &mut printed) {
+            Err(err) if !crate::errors::is_reported(&err) => eprintln!("wc: {}", err),
+            _ => (),
+        }
+
+        // Block until something other than a plain read happened to one of the files.
+        loop {
+            match rx.recv() {
+                Ok(Ok(event)) if !event.kind.is_access() => break,
+                Ok(Ok(_)) => continue,
+                Ok(Err(err)) => eprintln!("wc: {}", err),
+                Err(err) => return Err(Error::other(err)),
+            }
+        }
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+    }
+}
+
+// What tells two files apart even if they had the same name, if the system has it
+#[cfg(unix)]
+fn identity(meta: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn identity(_meta: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+//
+// A file that is followed by its name, like tail -F, so that a log that was rotated
+// is followed further in the new file. The counts of all files it was go on.
+//
+struct Followed {
+    path: String,
+    f: File,
+    identity: Option<(u64, u64)>,
+    // What was read of the file that is open
+    position: u64,
+    counter: Counter,
+}
+
+impl Followed {
+    fn open(path: &str, opts: &CountOptions) -> Result<Followed, Error> {
+        let f = File::open(path)?;
+        Ok(Followed {
+            path: path.to_owned(),
+            identity: identity(&f.metadata()?),
+            f,
+            position: 0,
+            counter: Counter::new(path, opts),
+        })
+    }
+
+    fn read_appended(&mut self, buffer: &mut [u8]) -> Result<bool, Error> {
+        let mut read_any = false;
+        loop {
+            let n = self.f.read(buffer)?;
+            if n == 0 {
+                return Ok(read_any);
+            }
+            self.counter.update(&buffer[..n])?;
+            self.position += n as u64;
+            read_any = true;
+        }
+    }
+
+    // Count what was appended, also to a file that was rotated or truncated since.
+    // Returns whether anything was.
+    fn poll(&mut self, buffer: &mut [u8]) -> Result<bool, Error> {
+        let mut read_any = self.read_appended(buffer)?;
+        // Gone for the moment, e.g. between renaming it and creating the new file
+        let Ok(meta) = std::fs::metadata(&self.path) else {
+            return Ok(read_any);
+        };
+        if identity(&meta) != self.identity {
+            log::info!("{}: replaced, following the new file", self.path);
+            self.f = File::open(&self.path)?;
+            self.identity = identity(&self.f.metadata()?);
+            self.position = 0;
+            read_any |= self.read_appended(buffer)?;
+        } else if meta.len() < self.position {
+            log::info!("{}: truncated, following from the start", self.path);
+            self.f.seek(SeekFrom::Start(0))?;
+            self.position = 0;
+            read_any |= self.read_appended(buffer)?;
+        }
+        Ok(read_any)
+    }
+}
+
+//
+// Lines and bytes per second over the last window of time, from the counts at each check
+//
+struct Rate {
+    window: Duration,
+    samples: VecDeque<(Instant, usize, usize)>,
+}
+
+impl Rate {
+    fn new(window: Duration) -> Rate {
+        Rate {
+            window,
+            samples: VecDeque::new(),
+        }
+    }
+
+    fn sample(&mut self, now: Instant, m: &Metrics) {
+        self.samples.push_back((now, m.lines, m.bytes));
+        // Keep the last sample from before the window, the rate is measured from there
+        while self
+            .samples
+            .get(1)
+            .is_some_and(|(then, _, _)| now.duration_since(*then) >= self.window)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    fn per_second(&self) -> (f64, f64) {
+        match (self.samples.front(), self.samples.back()) {
+            (Some(first), Some(last)) if last.0 > first.0 => {
+                let seconds = last.0.duration_since(first.0).as_secs_f64();
+                (
+                    (last.1 - first.1) as f64 / seconds,
+                    (last.2 - first.2) as f64 / seconds,
+                )
+            }
+            _ => (0.0, 0.0),
+        }
+    }
+
+    // The file name with the rates
+    fn label(&self, filename: &str) -> String {
+        let (lines, bytes) = self.per_second();
+        crate::i18n::message(
+            "rate",
+            &[
+                ("label", filename.into()),
+                ("lines", format!("{:.1}", lines).into()),
+                ("bytes", format!("{:.0}", bytes).into()),
+            ],
+        )
+    }
+}
+
+//
+// Keep the files open and count only what is appended to them,
+// reprinting the table whenever new data arrived.
+// Runs until the process is interrupted.
+//
+pub fn follow(cli: &Cli) -> Result<(), Error> {
+    let files = required_files(cli, "--follow")?;
+    let mut opts = ShowOptions::from_cli(cli)?;
+    let count_opts = CountOptions::from_cli(cli)?;
+
+    let mut followed = vec![];
+    let mut rates = vec![];
+    let mut printed: Vec<Metrics> = vec![];
+    for file in files {
+        followed.push(Followed::open(&file, &count_opts)?);
+        rates.extend(cli.rate.map(Rate::new));
+    }
+    let mut buffer = vec![0; 64 * 1024];
+    let mut changed = true;
+    loop {
+        for file in &mut followed {
+            changed |= file.poll(&mut buffer)?;
+        }
+        let mut all_metrics: Vec<Metrics> = followed
+            .iter()
+            .map(|file| file.counter.metrics().clone())
+            .collect();
+        // Rates change even if nothing was appended
+        let now = Instant::now();
+        for (rate, m) in rates.iter_mut().zip(&mut all_metrics) {
+            rate.sample(now, m);
+            m.filename = rate.label(&m.filename);
+            changed = true;
+        }
+        if changed {
+            if cli.live {
+                // The same files in the same order, only the rates in their labels change
+                let mut before = std::mem::take(&mut printed);
+                for (b, m) in before.iter_mut().zip(&all_metrics) {
+                    b.filename.clone_from(&m.filename);
+                }
+                opts.changed_since = Some(before);
+                printed.clone_from(&all_metrics);
+                print!("\x1b[2J\x1b[H");
+            }
+            crate::print_table(&mut io::stdout().lock(), &all_metrics, &opts);
+            changed = false;
+        }
+        thread::sleep(cli.interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testfile::TempPath;
+
+    #[test]
+    fn rates() {
+        let mut rate = Rate::new(Duration::from_secs(10));
+        let start = Instant::now();
+        let counts = |lines, bytes| Metrics {
+            lines,
+            bytes,
+            ..Default::default()
+        };
+        assert_eq!(rate.per_second(), (0.0, 0.0));
+        rate.sample(start, &counts(0, 0));
+        rate.sample(start + Duration::from_secs(5), &counts(50, 1000));
+        assert_eq!(rate.per_second(), (10.0, 200.0));
+        // The first five seconds drop out of the window
+        rate.sample(start + Duration::from_secs(15), &counts(60, 1200));
+        assert_eq!(rate.per_second(), (1.0, 20.0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rotated_and_truncated() {
+        let log = TempPath::new("follow.log");
+        let rotated = TempPath::new("follow.log.1");
+        let path = log.name();
+        let lines = |file: &Followed| file.counter.metrics().lines;
+        let mut buffer = vec![0; 16];
+
+        std::fs::write(&log, "one\ntwo\n").unwrap();
+        let mut file = Followed::open(path, &CountOptions::default()).unwrap();
+        assert!(file.poll(&mut buffer).unwrap());
+        assert_eq!(lines(&file), 2);
+
+        // Written to just before it is rotated
+        let mut old = std::fs::OpenOptions::new().append(true).open(&log).unwrap();
+        std::fs::rename(&log, &rotated).unwrap();
+        old.write_all(b"three\n").unwrap();
+        std::fs::write(&log, "four\n").unwrap();
+        assert!(file.poll(&mut buffer).unwrap());
+        assert_eq!(lines(&file), 4);
+        assert!(!file.poll(&mut buffer).unwrap());
+
+        // Truncated, as far as can be told by its length
+        std::fs::write(&log, "5\n").unwrap();
+        assert!(file.poll(&mut buffer).unwrap());
+        assert_eq!(lines(&file), 5);
+    }
+}
@@ -0,0 +1,85 @@
+//
+// Words to count the occurrences of, for --count-words. All of them are looked for at once
+// in each line with Aho-Corasick; only matches that are whole words are counted.
+//
+use aho_corasick::AhoCorasick;
+use std::fs;
+use std::io::Error;
+
+#[derive(Clone, Debug)]
+pub struct WordList {
+    matcher: AhoCorasick,
+    ignore_case: bool,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl WordList {
+    // The words listed in the file, one per line
+    pub fn load(path: &str, ignore_case: bool) -> Result<WordList, Error> {
+        let words: Vec<String> = fs::read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|word| !word.is_empty())
+            .map(|word| {
+                if ignore_case {
+                    word.to_lowercase()
+                } else {
+                    word.to_owned()
+                }
+            })
+            .collect();
+        Ok(WordList {
+            matcher: AhoCorasick::new(&words).map_err(Error::other)?,
+            ignore_case,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.matcher.patterns_len()
+    }
+
+    // Add the occurrences in a line to the counts, one per listed word
+    pub fn count(&self, line: &str, counts: &mut [usize]) {
+        let folded;
+        let line = if self.ignore_case {
+            folded = line.to_lowercase();
+            &folded
+        } else {
+            line
+        };
+        for found in self.matcher.find_overlapping_iter(line) {
+            let before = line[..found.start()].chars().next_back();
+            let after = line[found.end()..].chars().next();
+            if !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char) {
+                counts[found.pattern().as_usize()] += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testfile::TempPath;
+
+    #[test]
+    fn whole_words() {
+        let file = TempPath::file("words", "cat\nthe cat\n\nDog\n");
+        let path = file.name();
+        let (exact, folded) = (
+            WordList::load(path, false).unwrap(),
+            WordList::load(path, true).unwrap(),
+        );
+        assert_eq!(exact.len(), 3);
+        let line = "The cat, the dog and the cats. Dog: the cat!";
+        let mut counts = vec![0; 3];
+        exact.count(line, &mut counts);
+        assert_eq!(counts, [2, 1, 1]);
+        let mut counts = vec![0; 3];
+        folded.count(line, &mut counts);
+        assert_eq!(counts, [2, 2, 2]);
+    }
+}